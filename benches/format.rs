@@ -0,0 +1,54 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use rt_format::prelude::*;
+
+const FORMAT: &str = "[{}] {} requested {:>8} bytes from {:?} in {:.3}s ({:#x})";
+
+fn arguments() -> Vec<Primitive<'static>> {
+    vec![
+        "INFO".into(),
+        "client".into(),
+        4096u32.into(),
+        "/var/data".into(),
+        0.0125.into(),
+        48879u32.into(),
+    ]
+}
+
+fn parse_and_format(c: &mut Criterion) {
+    let args = arguments();
+    c.bench_function("parse and format", |b| {
+        b.iter(|| {
+            let parsed = ParsedFormat::parse(black_box(FORMAT), &args, &NoNamedArguments).unwrap();
+            parsed.to_string()
+        })
+    });
+}
+
+fn bind_template(c: &mut Criterion) {
+    let args = arguments();
+    let template = Template::parse(FORMAT).unwrap();
+    c.bench_function("bind template and format", |b| {
+        b.iter(|| template.bind(black_box(&args), &NoNamedArguments).unwrap().to_string())
+    });
+}
+
+// The `{}` and `{:?}` substitutions skip `format_argument` through the `Shortcut` of the parser.
+// Median times without it (by always choosing `Shortcut::None`) and with it, in three runs on a
+// single, noisy core:
+// * format parsed without specifiers: 338 -> 183 ns, 261 -> 242 ns, 381 -> 209 ns.
+// * format parsed: 945 -> 752 ns, 781 -> 709 ns, 963 -> 996 ns, i.e. no consistent change.
+// * parse and format: 2.38 -> 2.43 us, 2.39 -> 2.36 us; bind template and format: 2.39 -> 2.45 us,
+//   1.48 -> 1.68 us. Neither changes beyond the noise.
+fn format_parsed(c: &mut Criterion) {
+    let args = arguments();
+    let parsed = ParsedFormat::parse(FORMAT, &args, &NoNamedArguments).unwrap();
+    c.bench_function("format parsed", |b| b.iter(|| black_box(&parsed).to_string()));
+
+    let plain = ParsedFormat::parse("{} {} {} {}", &args, &NoNamedArguments).unwrap();
+    c.bench_function("format parsed without specifiers", |b| {
+        b.iter(|| black_box(&plain).to_string())
+    });
+}
+
+criterion_group!(benches, parse_and_format, bind_template, format_parsed);
+criterion_main!(benches);
@@ -0,0 +1,193 @@
+#![warn(missing_docs)]
+
+//! Derive macro for the `FormatArgument` trait of the `rt-format` crate.
+//!
+//! Use it through the `derive` feature of `rt-format`, which re-exports the macro alongside the
+//! trait.
+
+use proc_macro::TokenStream;
+use proc_macro2::{Span, TokenStream as TokenStream2};
+use quote::quote;
+use syn::{parse_macro_input, Data, DeriveInput, Error, Fields, LitStr, Variant};
+
+/// The formats that can be skipped, with the `Format` variant and the `std::fmt` trait for each.
+const FORMATS: &[(&str, &str, &str)] = &[
+    ("display", "Display", "Display"),
+    ("debug", "Debug", "Debug"),
+    ("octal", "Octal", "Octal"),
+    ("lower_hex", "LowerHex", "LowerHex"),
+    ("upper_hex", "UpperHex", "UpperHex"),
+    ("binary", "Binary", "Binary"),
+    ("lower_exp", "LowerExp", "LowerExp"),
+    ("upper_exp", "UpperExp", "UpperExp"),
+];
+
+/// Derives `FormatArgument` for an enum whose variants each wrap a single value.
+///
+/// Every format except the pointer format is delegated to the corresponding `std::fmt` trait of the
+/// wrapped value, so the wrapped types have to implement all of them. The pointer format is never
+/// supported, and neither are custom formats. Without an alignment, the wrapped value is padded the
+/// way its `std::fmt` implementation pads it.
+///
+/// A variant can opt out of some formats with `#[rt_format(skip = "octal,binary")]`, in which case
+/// its `supports_format` rejects those formats and the wrapped type doesn't need to implement their
+/// traits. The format names are `display`, `debug`, `octal`, `lower_hex`, `upper_hex`, `binary`,
+/// `lower_exp`, and `upper_exp`.
+///
+/// A variant is numeric, so that grouping and the locale apply to it, unless it skips all of the
+/// `octal`, `lower_hex`, `upper_hex`, `binary`, `lower_exp` and `upper_exp` formats, which only
+/// numbers support.
+///
+/// A variant annotated with `#[rt_format(size)]` can be used as width or precision. Its value is
+/// cloned and converted with `TryFrom`, so the wrapped type has to implement `Clone` and `usize`
+/// has to implement `TryFrom` for it. The other variants can't be used as width or precision.
+#[proc_macro_derive(FormatArgument, attributes(rt_format))]
+pub fn derive_format_argument(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(input)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+/// The options given to a variant through the `rt_format` attribute.
+struct VariantOptions {
+    skip: Vec<usize>,
+    size: bool,
+}
+
+fn parse_options(variant: &Variant) -> syn::Result<VariantOptions> {
+    let mut options = VariantOptions {
+        skip: Vec::new(),
+        size: false,
+    };
+    for attr in variant.attrs.iter().filter(|attr| attr.path().is_ident("rt_format")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("skip") {
+                let names: LitStr = meta.value()?.parse()?;
+                for name in names.value().split(',').map(str::trim) {
+                    match FORMATS.iter().position(|(format, _, _)| *format == name) {
+                        Some(idx) => options.skip.push(idx),
+                        None => {
+                            return Err(Error::new(
+                                names.span(),
+                                format!("unknown format `{}`", name),
+                            ))
+                        }
+                    }
+                }
+                Ok(())
+            } else if meta.path.is_ident("size") {
+                options.size = true;
+                Ok(())
+            } else {
+                Err(meta.error("expected `skip` or `size`"))
+            }
+        })?;
+    }
+    Ok(options)
+}
+
+fn expand(input: DeriveInput) -> syn::Result<TokenStream2> {
+    let data = match &input.data {
+        Data::Enum(data) => data,
+        _ => {
+            return Err(Error::new(
+                Span::call_site(),
+                "FormatArgument can only be derived for enums",
+            ))
+        }
+    };
+
+    let mut variants = Vec::new();
+    for variant in &data.variants {
+        match &variant.fields {
+            Fields::Unnamed(fields) if fields.unnamed.len() == 1 => {}
+            _ => {
+                return Err(Error::new_spanned(
+                    variant,
+                    "each variant must wrap exactly one unnamed field",
+                ))
+            }
+        }
+        variants.push((&variant.ident, parse_options(variant)?));
+    }
+
+    let supports_arms = variants.iter().map(|(ident, options)| {
+        let skipped = options.skip.iter().map(|idx| {
+            let format = syn::Ident::new(FORMATS[*idx].1, Span::call_site());
+            quote! { | ::rt_format::Format::#format }
+        });
+        quote! {
+            Self::#ident(_) => !::core::matches!(
+                specifier.format,
+                ::rt_format::Format::Pointer | ::rt_format::Format::Custom(_) #(#skipped)*
+            ),
+        }
+    });
+
+    let fmt_methods = FORMATS.iter().enumerate().map(|(idx, (format, _, fmt_trait))| {
+        let method = syn::Ident::new(&format!("fmt_{}", format), Span::call_site());
+        let fmt_trait = syn::Ident::new(fmt_trait, Span::call_site());
+        let arms = variants.iter().map(|(ident, options)| {
+            if options.skip.contains(&idx) {
+                quote! { Self::#ident(_) => ::core::result::Result::Err(::core::fmt::Error), }
+            } else {
+                quote! { Self::#ident(val) => ::core::fmt::#fmt_trait::fmt(val, f), }
+            }
+        });
+        quote! {
+            fn #method(&self, f: &mut ::core::fmt::Formatter) -> ::core::fmt::Result {
+                match self {
+                    #(#arms)*
+                }
+            }
+        }
+    });
+
+    let numeric_arms = variants.iter().map(|(ident, options)| {
+        let numeric = (2..FORMATS.len()).any(|idx| !options.skip.contains(&idx));
+        quote! { Self::#ident(_) => #numeric, }
+    });
+
+    let size_arms = variants.iter().map(|(ident, options)| {
+        if options.size {
+            quote! {
+                Self::#ident(val) => ::core::convert::TryFrom::try_from(
+                    ::core::clone::Clone::clone(val)
+                ).map_err(|_| ()),
+            }
+        } else {
+            quote! { Self::#ident(_) => ::core::result::Result::Err(()), }
+        }
+    });
+
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics ::rt_format::FormatArgument for #name #ty_generics #where_clause {
+            fn supports_format(&self, specifier: &::rt_format::Specifier) -> bool {
+                match self {
+                    #(#supports_arms)*
+                }
+            }
+
+            #(#fmt_methods)*
+
+            fn natural_alignment(&self) -> ::rt_format::Align {
+                ::rt_format::Align::None
+            }
+
+            fn is_numeric(&self) -> bool {
+                match self {
+                    #(#numeric_arms)*
+                }
+            }
+
+            fn to_usize(&self) -> ::core::result::Result<usize, ()> {
+                match self {
+                    #(#size_arms)*
+                }
+            }
+        }
+    })
+}
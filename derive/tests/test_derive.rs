@@ -0,0 +1,52 @@
+use rt_format::{FormatArgument, NoNamedArguments, ParsedFormat};
+
+#[derive(Debug, FormatArgument)]
+enum Value {
+    #[rt_format(size)]
+    Int(i32),
+    #[rt_format(skip = "octal, lower_hex, upper_hex, binary")]
+    Float(f64),
+    #[rt_format(skip = "octal,lower_hex,upper_hex,binary,lower_exp,upper_exp")]
+    Text(String),
+}
+
+fn fmt_args(spec: &str, args: &[Value]) -> String {
+    format!("{}", ParsedFormat::parse(spec, args, &NoNamedArguments).unwrap())
+}
+
+#[test]
+fn delegates_formats() {
+    assert_eq!(
+        "0x2a 4.2e1 42.042 \"foo\"",
+        fmt_args(
+            "{:#x} {0:e} {} {:?}",
+            &[Value::Int(42), Value::Float(42.042), Value::Text("foo".to_string())]
+        )
+    );
+}
+
+#[test]
+fn skipped_formats() {
+    let args = [Value::Float(42.042), Value::Text("foo".to_string())];
+    assert!(ParsedFormat::parse("{:x}", &args, &NoNamedArguments).is_err());
+    assert!(ParsedFormat::parse("{1:e}", &args, &NoNamedArguments).is_err());
+    assert!(ParsedFormat::parse("{0:e} {1:?}", &args, &NoNamedArguments).is_ok());
+}
+
+#[test]
+fn numeric_variants() {
+    let args = [Value::Int(1234), Value::Float(1234.5), Value::Text("2024".to_string())];
+    assert_eq!("1,234 1,234.5 2024", fmt_args("{:,} {:,} {:,}", &args));
+}
+
+#[test]
+fn size_variants() {
+    assert_eq!(
+        "[   42]",
+        fmt_args("[{:1$}]", &[Value::Int(42), Value::Int(5)])
+    );
+    assert!(ParsedFormat::parse("{:1$}", &[Value::Int(42), Value::Float(5.0)], &NoNamedArguments)
+        .is_err());
+    assert!(ParsedFormat::parse("{:1$}", &[Value::Int(42), Value::Int(-5)], &NoNamedArguments)
+        .is_err());
+}
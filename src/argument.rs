@@ -1,42 +1,248 @@
 //! Defines traits and types to help make arbitrary values formattable at runtime.
 
-use std::borrow::Borrow;
+use alloc::borrow::Cow;
+use alloc::boxed::Box;
+use alloc::collections::BTreeMap;
+use alloc::rc::Rc;
+use alloc::string::{String, ToString};
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::borrow::Borrow;
+use core::cell::RefCell;
+#[cfg(any(feature = "std", feature = "serde_json"))]
+use core::convert::TryFrom;
+use core::convert::TryInto;
+use core::fmt::{self, Write};
+use core::iter::FromIterator;
+use core::marker::PhantomData;
+use core::time::Duration;
+#[cfg(feature = "std")]
 use std::collections::HashMap;
-use std::fmt;
+#[cfg(feature = "std")]
 use std::hash::Hash;
+#[cfg(feature = "std")]
+use std::time::{SystemTime, SystemTimeError};
 
-use crate::Specifier;
+use crate::{format_value, Align, Format, Grouping, Locale, Repr, Specifier, Width};
 
 /// A type that indicates whether its value supports a specific format, and provides formatting
 /// functions that correspond to different format types.
+///
+/// All the methods have default implementations, so an implementor only needs to override the
+/// ones for the formats it supports. The default `supports_format` accepts only the display
+/// format, and the default formatting functions always return an error.
 pub trait FormatArgument {
     /// Returns `true` if `self` can be formatted using the given specifier.
-    fn supports_format(&self, specifier: &Specifier) -> bool;
+    ///
+    /// The whole specifier is given, not just the format, so an argument can also reject flags it
+    /// can't honor, e.g. `Sign::Always` for a string. A flag that's accepted is only passed on
+    /// through the formatter, so it's up to the formatting function whether it has any effect, just
+    /// like `{:+}` has no effect on a `str` in `format!`.
+    fn supports_format(&self, specifier: &Specifier) -> bool {
+        specifier.format == Format::Display
+    }
     /// Formats the value the way it would be formatted if it implemented `std::fmt::Display`.
-    fn fmt_display(&self, f: &mut fmt::Formatter) -> fmt::Result;
+    fn fmt_display(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let _ = f;
+        Err(fmt::Error)
+    }
+    /// Formats the value with the display format, given the whole specifier. This is called instead
+    /// of `fmt_display` when formatting through this crate, so that an argument can adapt to the
+    /// requested width, precision or alignment, e.g. by abbreviating itself when the width is
+    /// small. The default implementation ignores the specifier and calls `fmt_display`.
+    fn fmt_display_with(&self, specifier: &Specifier, f: &mut fmt::Formatter) -> fmt::Result {
+        let _ = specifier;
+        self.fmt_display(f)
+    }
     /// Formats the value the way it would be formatted if it implemented `std::fmt::Debug`.
-    fn fmt_debug(&self, f: &mut fmt::Formatter) -> fmt::Result;
+    fn fmt_debug(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let _ = f;
+        Err(fmt::Error)
+    }
+    /// Formats the value with the pretty debug format and a width, e.g. `{:#8?}`. This is an
+    /// advanced hook for arguments whose pretty debug output spans several lines: `std::fmt`
+    /// ignores the width of such output, so an argument can use it instead as the number of spaces
+    /// by which to indent the nested lines, e.g. to line them up with a log prefix. The width is
+    /// also still set in the formatter. The default implementation ignores the indent and calls
+    /// `fmt_debug`, which gives the same output as `format!`.
+    fn fmt_debug_indented(&self, indent: usize, f: &mut fmt::Formatter) -> fmt::Result {
+        let _ = indent;
+        self.fmt_debug(f)
+    }
     /// Formats the value the way it would be formatted if it implemented `std::fmt::Octal`.
-    fn fmt_octal(&self, f: &mut fmt::Formatter) -> fmt::Result;
+    fn fmt_octal(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let _ = f;
+        Err(fmt::Error)
+    }
     /// Formats the value the way it would be formatted if it implemented `std::fmt::LowerHex`.
-    fn fmt_lower_hex(&self, f: &mut fmt::Formatter) -> fmt::Result;
+    fn fmt_lower_hex(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let _ = f;
+        Err(fmt::Error)
+    }
     /// Formats the value the way it would be formatted if it implemented `std::fmt::UpperHex`.
-    fn fmt_upper_hex(&self, f: &mut fmt::Formatter) -> fmt::Result;
+    fn fmt_upper_hex(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let _ = f;
+        Err(fmt::Error)
+    }
     /// Formats the value the way it would be formatted if it implemented `std::fmt::Binary`.
-    fn fmt_binary(&self, f: &mut fmt::Formatter) -> fmt::Result;
+    fn fmt_binary(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let _ = f;
+        Err(fmt::Error)
+    }
     /// Formats the value the way it would be formatted if it implemented `std::fmt::LowerExp`.
-    fn fmt_lower_exp(&self, f: &mut fmt::Formatter) -> fmt::Result;
+    fn fmt_lower_exp(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let _ = f;
+        Err(fmt::Error)
+    }
     /// Formats the value the way it would be formatted if it implemented `std::fmt::UpperExp`.
-    fn fmt_upper_exp(&self, f: &mut fmt::Formatter) -> fmt::Result;
+    fn fmt_upper_exp(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let _ = f;
+        Err(fmt::Error)
+    }
+    /// Formats the value the way it would be formatted if it implemented `std::fmt::Pointer`.
+    fn fmt_pointer(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let _ = f;
+        Err(fmt::Error)
+    }
+    /// Formats the value using the custom format with the given letter, e.g. `c` for `{:c}`. Only
+    /// called if `supports_format` accepted the `Format::Custom` with that letter. The formatter
+    /// carries the width, precision and the rest of the specifier, as it does for the other
+    /// formats.
+    fn fmt_custom(&self, tag: char, f: &mut fmt::Formatter) -> fmt::Result {
+        let _ = (tag, f);
+        Err(fmt::Error)
+    }
+    /// Returns the alignment to use when a width is given without an alignment. The default,
+    /// `Align::None`, leaves the alignment to the formatting function, which is right when it
+    /// delegates to a `std::fmt` implementation that already pads the value. An argument that pads
+    /// its output some other way can return `Align::Right` for numbers and `Align::Left` for text,
+    /// like `format!` aligns them.
+    fn natural_alignment(&self) -> Align {
+        Align::None
+    }
+
+    /// Returns `true` if the value is a number, so that it's grouped when the specifier asks for
+    /// grouping, as in `{:,}`, and written with the separators of the `Locale`. Both are ignored
+    /// for any other value, so that e.g. a date such as `2024-01-01` or a year written as text
+    /// stays as it is. The default implementation treats a value as a number if its natural
+    /// alignment is to the right.
+    fn is_numeric(&self) -> bool {
+        self.natural_alignment() == Align::Right
+    }
+
     /// Performs a type conversion into `usize` that might fail. Like `TryInto<usize>`, but does not
     /// consume `self`. The parser uses this to support formats whose width or precision use "dollar
-    /// syntax". For more information about these, see [std::fmt]. The default implementation always
-    /// returns an error.
+    /// syntax" or `.*`. For more information about these, see [std::fmt]. The default
+    /// implementation always returns an error, so an argument can't supply a width or precision
+    /// unless its type overrides this method. No other trait needs to be implemented for this.
+    #[allow(clippy::result_unit_err)]
     fn to_usize(&self) -> Result<usize, ()> {
         Err(())
     }
 }
 
+/// Implements `FormatArgument` for a pointer type by forwarding every method to the pointee.
+macro_rules! forward_format_argument {
+    ($([$($generics:tt)*] $ptr:ty),+) => {
+        $(
+            impl<$($generics)*> FormatArgument for $ptr {
+                fn supports_format(&self, specifier: &Specifier) -> bool {
+                    (**self).supports_format(specifier)
+                }
+                fn fmt_display(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                    (**self).fmt_display(f)
+                }
+                fn fmt_display_with(
+                    &self,
+                    specifier: &Specifier,
+                    f: &mut fmt::Formatter,
+                ) -> fmt::Result {
+                    (**self).fmt_display_with(specifier, f)
+                }
+                fn fmt_debug(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                    (**self).fmt_debug(f)
+                }
+                fn fmt_debug_indented(&self, indent: usize, f: &mut fmt::Formatter) -> fmt::Result {
+                    (**self).fmt_debug_indented(indent, f)
+                }
+                fn fmt_octal(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                    (**self).fmt_octal(f)
+                }
+                fn fmt_lower_hex(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                    (**self).fmt_lower_hex(f)
+                }
+                fn fmt_upper_hex(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                    (**self).fmt_upper_hex(f)
+                }
+                fn fmt_binary(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                    (**self).fmt_binary(f)
+                }
+                fn fmt_lower_exp(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                    (**self).fmt_lower_exp(f)
+                }
+                fn fmt_upper_exp(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                    (**self).fmt_upper_exp(f)
+                }
+                fn fmt_pointer(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                    (**self).fmt_pointer(f)
+                }
+                fn fmt_custom(&self, tag: char, f: &mut fmt::Formatter) -> fmt::Result {
+                    (**self).fmt_custom(tag, f)
+                }
+                fn natural_alignment(&self) -> Align {
+                    (**self).natural_alignment()
+                }
+                fn is_numeric(&self) -> bool {
+                    (**self).is_numeric()
+                }
+                fn to_usize(&self) -> Result<usize, ()> {
+                    (**self).to_usize()
+                }
+            }
+        )+
+    };
+}
+
+// These make trait objects usable as arguments, e.g. a `Vec<Box<dyn FormatArgument>>` or a
+// `&[&dyn FormatArgument]` can be passed as positional arguments, and let values that are borrowed
+// or shared through `Rc` or `Arc` be used without cloning them, e.g. in a `&[&V]` or a
+// `HashMap<String, Arc<V>>`. Since a map that holds references, such as a `HashMap<&str, &V>`,
+// implements `NamedArguments` for both `V` and `&V`, the argument type has to be spelled out when
+// such a map is used with `NoPositionalArguments`.
+forward_format_argument!(
+    ['a, T: FormatArgument + ?Sized] &'a T,
+    [T: FormatArgument + ?Sized] Box<T>,
+    [T: FormatArgument + ?Sized] Rc<T>,
+    [T: FormatArgument + ?Sized] Arc<T>
+);
+
+/// Implements `FormatArgument` for the given string types, which support the display and debug
+/// formats. Like in `format!`, the precision truncates the string. A string can supply the width or
+/// precision if it's a non-negative integer.
+macro_rules! string_format_argument {
+    ($([$($generics:tt)*] $type:ty),+) => {
+        $(
+            impl<$($generics)*> FormatArgument for $type {
+                fn supports_format(&self, specifier: &Specifier) -> bool {
+                    matches!(specifier.format, Format::Display | Format::Debug)
+                }
+                fn fmt_display(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                    fmt::Display::fmt(self, f)
+                }
+                fn fmt_debug(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                    fmt::Debug::fmt(self, f)
+                }
+                fn to_usize(&self) -> Result<usize, ()> {
+                    self.parse().map_err(|_| ())
+                }
+            }
+        )+
+    };
+}
+
+// A `&str` is covered by the impl for references.
+string_format_argument!([] str, [] String, ['a] Cow<'a, str>);
+
 /// Holds a `FormatArgument` and implements all the `std::fmt` formatting traits.
 pub struct ArgumentFormatter<'v, V: FormatArgument>(pub &'v V);
 
@@ -88,12 +294,341 @@ impl<'v, V: FormatArgument> fmt::UpperExp for ArgumentFormatter<'v, V> {
     }
 }
 
+impl<'v, V: FormatArgument> fmt::Pointer for ArgumentFormatter<'v, V> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.0.fmt_pointer(f)
+    }
+}
+
+/// Formats a value with a custom format, whatever `std::fmt` trait it is formatted through.
+struct CustomFormatter<'v, V: FormatArgument>(char, &'v V);
+
+impl<'v, V: FormatArgument> CustomFormatter<'v, V> {
+    fn format(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.1.fmt_custom(self.0, f)
+    }
+}
+
+/// Formats a value with `fmt_pointer`, whatever `std::fmt` trait it is formatted through.
+struct PointerFormatter<'v, V: FormatArgument>(&'v V);
+
+impl<'v, V: FormatArgument> PointerFormatter<'v, V> {
+    fn format(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.0.fmt_pointer(f)
+    }
+}
+
+/// Formats a value with `fmt_display_with`, whatever `std::fmt` trait it is formatted through.
+struct DisplayWithFormatter<'v, V: FormatArgument>(&'v Specifier, &'v V);
+
+impl<'v, V: FormatArgument> DisplayWithFormatter<'v, V> {
+    fn format(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.1.fmt_display_with(self.0, f)
+    }
+}
+
+/// Formats a value with `fmt_debug_indented`, whatever `std::fmt` trait it is formatted through.
+struct DebugIndentedFormatter<'v, V: FormatArgument>(usize, &'v V);
+
+impl<'v, V: FormatArgument> DebugIndentedFormatter<'v, V> {
+    fn format(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.1.fmt_debug_indented(self.0, f)
+    }
+}
+
+/// Implements all the `std::fmt` formatting traits for a wrapper with its `format` method.
+macro_rules! impl_single_formatter {
+    ($wrapper:ident: $($fmt_trait:ident),+) => {
+        $(
+            impl<'v, V: FormatArgument> fmt::$fmt_trait for $wrapper<'v, V> {
+                fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                    self.format(f)
+                }
+            }
+        )+
+    };
+}
+
+impl_single_formatter!(
+    CustomFormatter: Display, Debug, Octal, LowerHex, UpperHex, Binary, LowerExp, UpperExp
+);
+impl_single_formatter!(
+    PointerFormatter: Display, Debug, Octal, LowerHex, UpperHex, Binary, LowerExp, UpperExp
+);
+impl_single_formatter!(
+    DisplayWithFormatter: Display, Debug, Octal, LowerHex, UpperHex, Binary, LowerExp, UpperExp
+);
+impl_single_formatter!(
+    DebugIndentedFormatter: Display, Debug, Octal, LowerHex, UpperHex, Binary, LowerExp, UpperExp
+);
+
+/// Formats a `FormatArgument` with `format_value`. A custom format is formatted as display, with
+/// `fmt_custom` standing in for `fmt_display`, so that the rest of the specifier still applies, and
+/// so is the pointer format, with `fmt_pointer`, since `format_value` doesn't support it. The
+/// display format goes through `fmt_display_with`, and the pretty debug format with a width through
+/// `fmt_debug_indented`. If the specifier has no alignment, the natural alignment of the value is
+/// used. Grouping and the locale are ignored unless the value is numeric.
+pub(crate) fn format_argument<V: FormatArgument>(
+    specifier: &Specifier,
+    value: &V,
+    f: &mut fmt::Formatter,
+) -> fmt::Result {
+    let plain;
+    let specifier = if specifier.grouping == Grouping::None && specifier.locale == Locale::C
+        || value.is_numeric()
+    {
+        specifier
+    } else {
+        plain = Specifier { grouping: Grouping::None, locale: Locale::C, ..*specifier };
+        &plain
+    };
+    let aligned;
+    let specifier = match specifier.align {
+        Align::None => {
+            aligned = Specifier { align: value.natural_alignment(), ..*specifier };
+            &aligned
+        }
+        _ => specifier,
+    };
+    match specifier.format {
+        Format::Custom(tag) => {
+            let specifier = Specifier { format: Format::Display, ..*specifier };
+            format_value(&specifier, &CustomFormatter(tag, value), f)
+        }
+        Format::Pointer => {
+            let specifier = Specifier {
+                format: Format::Display,
+                grouping: Grouping::None,
+                locale: Locale::C,
+                ..*specifier
+            };
+            format_value(&specifier, &PointerFormatter(value), f)
+        }
+        Format::Display => format_value(specifier, &DisplayWithFormatter(specifier, value), f),
+        Format::Debug if specifier.repr == Repr::Alt => match specifier.width {
+            Width::AtLeast { width } => {
+                format_value(specifier, &DebugIndentedFormatter(width, value), f)
+            }
+            Width::Auto => format_value(specifier, &ArgumentFormatter(value), f),
+        },
+        _ => format_value(specifier, &ArgumentFormatter(value), f),
+    }
+}
+
+/// Formats a single value according to the given specifier, without going through a formatting
+/// string. Returns an error if the value doesn't support the format, or if its formatting function
+/// fails.
+#[allow(clippy::result_unit_err)]
+pub fn format_one<V: FormatArgument>(specifier: &Specifier, value: &V) -> Result<String, ()> {
+    if !value.supports_format(specifier) {
+        return Err(());
+    }
+    let mut result = String::new();
+    write!(result, "{}", SingleValue(specifier, value)).map_err(|_| ())?;
+    Ok(result)
+}
+
+/// Formats a `FormatArgument` with `format_value`.
+struct SingleValue<'a, V: FormatArgument>(&'a Specifier, &'a V);
+
+impl<'a, V: FormatArgument> fmt::Display for SingleValue<'a, V> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        format_argument(self.0, self.1, f)
+    }
+}
+
+/// Wraps a value that implements all the `std::fmt` formatting traits, and implements
+/// `FormatArgument` by delegating to them. Since few types implement `std::fmt::Pointer`, a
+/// `StdValue` doesn't support the pointer format. It doesn't support custom formats either.
+///
+/// A `StdValue` has no natural alignment of its own, so a value without an alignment is padded the
+/// way its `std::fmt` implementation pads it. It's always numeric, since the types that implement
+/// all the `std::fmt` traits are numbers.
+///
+/// The width and precision can be taken from a `StdValue` if the value converts to `usize` with
+/// `TryInto`, which is the case for a non-negative primitive integer that fits in `usize`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct StdValue<T>(pub T);
+
+impl<T> FormatArgument for StdValue<T>
+where
+    T: fmt::Display
+        + fmt::Debug
+        + fmt::Octal
+        + fmt::LowerHex
+        + fmt::UpperHex
+        + fmt::Binary
+        + fmt::LowerExp
+        + fmt::UpperExp
+        + Clone
+        + TryInto<usize>,
+{
+    fn supports_format(&self, specifier: &Specifier) -> bool {
+        !matches!(specifier.format, Format::Pointer | Format::Custom(_))
+    }
+
+    fn fmt_display(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(&self.0, f)
+    }
+
+    fn fmt_debug(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(&self.0, f)
+    }
+
+    fn fmt_octal(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Octal::fmt(&self.0, f)
+    }
+
+    fn fmt_lower_hex(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::LowerHex::fmt(&self.0, f)
+    }
+
+    fn fmt_upper_hex(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::UpperHex::fmt(&self.0, f)
+    }
+
+    fn fmt_binary(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Binary::fmt(&self.0, f)
+    }
+
+    fn fmt_lower_exp(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::LowerExp::fmt(&self.0, f)
+    }
+
+    fn fmt_upper_exp(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::UpperExp::fmt(&self.0, f)
+    }
+
+    fn natural_alignment(&self) -> Align {
+        Align::None
+    }
+
+    fn is_numeric(&self) -> bool {
+        true
+    }
+
+    fn to_usize(&self) -> Result<usize, ()> {
+        self.0.clone().try_into().map_err(|_| ())
+    }
+}
+
+/// Wraps a `Duration`, so that timing data can be formatted directly.
+///
+/// The display format writes the number of seconds, with as many decimal places as the precision
+/// asks for, or as many as it takes otherwise. The debug format is the one of `Duration`, e.g.
+/// `1.5s`. The custom format `h` writes the duration for humans, e.g. `1h23m` or `2m5s`, with each
+/// unit of hours, minutes and seconds that isn't zero. A duration shorter than a second is written
+/// in whole milliseconds, microseconds or nanoseconds instead, and a longer one drops the fraction
+/// of a second. Like in `format!`, a duration is aligned to the right by default.
+///
+/// With the `std` feature, a `SystemTime` converts to the `DurationValue` since the Unix epoch.
+///
+/// # Examples
+/// ```
+/// use rt_format::argument::DurationValue;
+/// use rt_format::{NoNamedArguments, ParsedFormat};
+/// use std::time::Duration;
+///
+/// let args = [DurationValue(Duration::from_millis(4_983_250))];
+/// let parsed = ParsedFormat::parse("{:.1}s {0:h} {0:?}", &args, &NoNamedArguments).unwrap();
+/// assert_eq!("4983.2s 1h23m3s 4983.25s", parsed.to_string());
+/// ```
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct DurationValue(pub Duration);
+
+impl DurationValue {
+    fn fmt_human(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let secs = self.0.as_secs();
+        let nanos = self.0.subsec_nanos();
+        let mut text = String::new();
+        if secs == 0 {
+            match nanos {
+                0 => text.push_str("0s"),
+                1_000_000.. => write!(text, "{}ms", nanos / 1_000_000)?,
+                1_000.. => write!(text, "{}µs", nanos / 1_000)?,
+                _ => write!(text, "{}ns", nanos)?,
+            }
+        } else {
+            for (amount, unit) in [(secs / 3600, 'h'), (secs / 60 % 60, 'm'), (secs % 60, 's')] {
+                if amount > 0 {
+                    write!(text, "{}{}", amount, unit)?;
+                }
+            }
+        }
+        f.pad(&text)
+    }
+}
+
+impl From<Duration> for DurationValue {
+    fn from(duration: Duration) -> Self {
+        DurationValue(duration)
+    }
+}
+
+#[cfg(feature = "std")]
+impl TryFrom<SystemTime> for DurationValue {
+    type Error = SystemTimeError;
+
+    /// Converts the time to the duration since the Unix epoch. Fails if the time is earlier.
+    fn try_from(time: SystemTime) -> Result<Self, Self::Error> {
+        time.duration_since(SystemTime::UNIX_EPOCH).map(DurationValue)
+    }
+}
+
+impl FormatArgument for DurationValue {
+    fn supports_format(&self, specifier: &Specifier) -> bool {
+        matches!(specifier.format, Format::Display | Format::Debug | Format::Custom('h'))
+    }
+
+    fn fmt_display(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(&self.0.as_secs_f64(), f)
+    }
+
+    fn fmt_debug(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(&self.0, f)
+    }
+
+    fn fmt_custom(&self, tag: char, f: &mut fmt::Formatter) -> fmt::Result {
+        match tag {
+            'h' => self.fmt_human(f),
+            _ => Err(fmt::Error),
+        }
+    }
+
+    fn natural_alignment(&self) -> Align {
+        Align::Right
+    }
+}
+
 /// A type that associates an argument with a name.
 pub trait NamedArguments<V: FormatArgument> {
     /// Returns a reference to the argument associated with the given name, if any.
     fn get(&self, key: &str) -> Option<&V>;
+
+    /// Returns a reference to the argument at the given path, if any. Dotted names in the
+    /// formatting string, such as `{order.customer.email}`, are split on the dots and looked up
+    /// through this method, so implementations can walk nested structures. Names without dots are
+    /// looked up with `get`.
+    ///
+    /// The default implementation resolves only single-segment paths, using `get`.
+    fn get_path(&self, path: &[&str]) -> Option<&V> {
+        match path {
+            [key] => self.get(key),
+            _ => None,
+        }
+    }
+
+    /// Returns the names of all the arguments. Only used to report the unused arguments when
+    /// parsing with `Parser::with_strict_arguments`.
+    ///
+    /// The default implementation returns no names, so none of the arguments are ever reported as
+    /// unused.
+    fn names(&self) -> Vec<&str> {
+        Vec::new()
+    }
 }
 
+#[cfg(feature = "std")]
 impl<K, V> NamedArguments<V> for HashMap<K, V>
 where
     K: Borrow<str> + Hash + Eq,
@@ -102,15 +637,272 @@ where
     fn get(&self, key: &str) -> Option<&V> {
         <HashMap<K, V>>::get(self, key)
     }
+
+    fn names(&self) -> Vec<&str> {
+        self.keys().map(Borrow::borrow).collect()
+    }
 }
 
+#[cfg(feature = "std")]
 impl<K, V> NamedArguments<V> for HashMap<K, &V>
 where
     K: Borrow<str> + Hash + Eq,
     V: FormatArgument,
 {
     fn get(&self, key: &str) -> Option<&V> {
-        <HashMap<K, &V>>::get(self, key).map(|v| *v)
+        <HashMap<K, &V>>::get(self, key).copied()
+    }
+
+    fn names(&self) -> Vec<&str> {
+        self.keys().map(Borrow::borrow).collect()
+    }
+}
+
+impl<K, V> NamedArguments<V> for BTreeMap<K, V>
+where
+    K: Borrow<str> + Ord,
+    V: FormatArgument,
+{
+    fn get(&self, key: &str) -> Option<&V> {
+        <BTreeMap<K, V>>::get(self, key)
+    }
+
+    fn names(&self) -> Vec<&str> {
+        self.keys().map(Borrow::borrow).collect()
+    }
+}
+
+impl<K, V> NamedArguments<V> for BTreeMap<K, &V>
+where
+    K: Borrow<str> + Ord,
+    V: FormatArgument,
+{
+    fn get(&self, key: &str) -> Option<&V> {
+        <BTreeMap<K, &V>>::get(self, key).copied()
+    }
+
+    fn names(&self) -> Vec<&str> {
+        self.keys().map(Borrow::borrow).collect()
+    }
+}
+
+/// Looks up the named argument with a linear scan, which is cheaper than building a map when
+/// there are only a few arguments. If the same name appears more than once, the first one is used.
+impl<K, V> NamedArguments<V> for [(K, V)]
+where
+    K: Borrow<str>,
+    V: FormatArgument,
+{
+    fn get(&self, key: &str) -> Option<&V> {
+        self.iter()
+            .find(|(name, _)| name.borrow() == key)
+            .map(|(_, value)| value)
+    }
+
+    fn names(&self) -> Vec<&str> {
+        self.iter().map(|(name, _)| name.borrow()).collect()
+    }
+}
+
+impl<K, V, const LEN: usize> NamedArguments<V> for [(K, V); LEN]
+where
+    K: Borrow<str>,
+    V: FormatArgument,
+{
+    fn get(&self, key: &str) -> Option<&V> {
+        <[(K, V)] as NamedArguments<V>>::get(self, key)
+    }
+
+    fn names(&self) -> Vec<&str> {
+        <[(K, V)] as NamedArguments<V>>::names(self)
+    }
+}
+
+/// Wraps a `NamedArguments` to make the lookup case-insensitive, so that `{Name}` and `{name}`
+/// refer to the same argument. The names in the formatting string are lowercased before they are
+/// looked up, so the wrapped arguments must use lowercase names.
+///
+/// Only ASCII letters are lowercased. Unicode case mapping can change the length of a name and
+/// depends on the language, so a non-ASCII name has to match exactly.
+#[derive(Debug, Copy, Clone, Default)]
+pub struct CaseInsensitiveArguments<N>(pub N);
+
+impl<V, N> NamedArguments<V> for CaseInsensitiveArguments<N>
+where
+    V: FormatArgument,
+    N: NamedArguments<V>,
+{
+    fn get(&self, key: &str) -> Option<&V> {
+        self.0.get(&key.to_ascii_lowercase())
+    }
+
+    fn get_path(&self, path: &[&str]) -> Option<&V> {
+        let lowercase: Vec<String> = path.iter().map(|key| key.to_ascii_lowercase()).collect();
+        let path: Vec<&str> = lowercase.iter().map(String::as_str).collect();
+        self.0.get_path(&path)
+    }
+
+    fn names(&self) -> Vec<&str> {
+        self.0.names()
+    }
+}
+
+/// Chains two `NamedArguments`, looking each name up in the first one and then, if it isn't
+/// there, in the second one. Useful for layering, e.g. request-specific arguments over global
+/// defaults, without building a merged map. Longer chains can be built by nesting.
+#[derive(Debug, Copy, Clone, Default)]
+pub struct OrElse<A, B>(pub A, pub B);
+
+impl<V, A, B> NamedArguments<V> for OrElse<A, B>
+where
+    V: FormatArgument,
+    A: NamedArguments<V>,
+    B: NamedArguments<V>,
+{
+    fn get(&self, key: &str) -> Option<&V> {
+        self.0.get(key).or_else(|| self.1.get(key))
+    }
+
+    fn get_path(&self, path: &[&str]) -> Option<&V> {
+        self.0.get_path(path).or_else(|| self.1.get_path(path))
+    }
+
+    fn names(&self) -> Vec<&str> {
+        let mut names = self.0.names();
+        for name in self.1.names() {
+            if !names.contains(&name) {
+                names.push(name);
+            }
+        }
+        names
+    }
+}
+
+/// Named arguments that are resolved by a closure, e.g. from the state of the application, without
+/// building a map first. The closure is called with each name as it's looked up. The values it
+/// returns have to outlive the arguments, so it can't return a reference to a value it computes.
+///
+/// Since the names aren't known in advance, `names` returns none of them, so none of the arguments
+/// are reported as unused by `Parser::with_strict_arguments`.
+///
+/// # Examples
+/// ```
+/// use rt_format::argument::FnNamedArguments;
+/// use rt_format::{NoPositionalArguments, ParsedFormat, StdValue};
+///
+/// let (width, height) = (StdValue(640), StdValue(480));
+/// let named = FnNamedArguments::new(|name: &str| match name {
+///     "width" => Some(&width),
+///     "height" => Some(&height),
+///     _ => None,
+/// });
+/// let parsed = ParsedFormat::parse("{width}x{height}", &NoPositionalArguments, &named).unwrap();
+/// assert_eq!("640x480", parsed.to_string());
+/// ```
+pub struct FnNamedArguments<'a, V, F>(F, PhantomData<&'a V>);
+
+impl<'a, V, F> FnNamedArguments<'a, V, F>
+where
+    F: Fn(&str) -> Option<&'a V>,
+{
+    /// Creates named arguments that are resolved by the given closure.
+    pub fn new(resolve: F) -> Self {
+        FnNamedArguments(resolve, PhantomData)
+    }
+}
+
+impl<'a, V, F> NamedArguments<V> for FnNamedArguments<'a, V, F>
+where
+    V: 'a + FormatArgument,
+    F: Fn(&str) -> Option<&'a V>,
+{
+    fn get(&self, key: &str) -> Option<&V> {
+        (self.0)(key)
+    }
+}
+
+/// Named arguments that the application sets one at a time, like variables that a formatting string
+/// can refer to by name, e.g. `{x}`. It's the closest runtime analog to the identifiers that
+/// `format!` captures from the surrounding code.
+///
+/// A scope created with `child` sees the arguments of its parent, except the ones it sets itself.
+/// The arguments are shared between the clones of a scope, and between a scope and its children,
+/// so cloning a scope or creating a child doesn't copy them. Setting an argument in a scope whose
+/// arguments are shared copies them first, so it never affects the other scopes.
+#[cfg(feature = "std")]
+#[derive(Debug)]
+pub struct Scope<V> {
+    values: Rc<HashMap<String, V>>,
+    parent: Option<Rc<Scope<V>>>,
+}
+
+#[cfg(feature = "std")]
+impl<V> Scope<V> {
+    /// Creates a scope without any arguments and without a parent.
+    pub fn new() -> Self {
+        Scope {
+            values: Rc::new(HashMap::new()),
+            parent: None,
+        }
+    }
+
+    /// Creates an empty scope whose parent is this one.
+    pub fn child(&self) -> Self {
+        Scope {
+            values: Rc::new(HashMap::new()),
+            parent: Some(Rc::new(self.clone())),
+        }
+    }
+
+    /// Sets the argument with the given name in this scope, shadowing the parent's argument with
+    /// the same name, if any. Returns the previous value of the argument in this scope, if any.
+    pub fn set(&mut self, name: impl Into<String>, value: V) -> Option<V>
+    where
+        V: Clone,
+    {
+        Rc::make_mut(&mut self.values).insert(name.into(), value)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<V> Clone for Scope<V> {
+    fn clone(&self) -> Self {
+        Scope {
+            values: Rc::clone(&self.values),
+            parent: self.parent.clone(),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<V> Default for Scope<V> {
+    fn default() -> Self {
+        Scope::new()
+    }
+}
+
+#[cfg(feature = "std")]
+impl<V> NamedArguments<V> for Scope<V>
+where
+    V: FormatArgument,
+{
+    fn get(&self, key: &str) -> Option<&V> {
+        match self.values.get(key) {
+            Some(value) => Some(value),
+            None => self.parent.as_ref().and_then(|parent| parent.get(key)),
+        }
+    }
+
+    fn names(&self) -> Vec<&str> {
+        let mut names: Vec<&str> = self.values.keys().map(String::as_str).collect();
+        if let Some(parent) = &self.parent {
+            for name in parent.names() {
+                if !self.values.contains_key(name) {
+                    names.push(name);
+                }
+            }
+        }
+        names
     }
 }
 
@@ -127,6 +919,13 @@ where
 }
 
 /// A type that provides a list of arguments, randomly accessible by their position.
+///
+/// It's implemented for every type that implements `AsRef<[V]>`. That covers slices, arrays,
+/// `Vec<V>` and `Box<[V]>`, as well as inline containers from other crates, such as `SmallVec` and
+/// `ArrayVec`, so a handful of arguments doesn't need to be allocated. A collection of references,
+/// such as `&[&V]`, can be used directly, with `&V` as the argument type, or through
+/// `BorrowedArguments`, with `V` as the argument type. Use `CombinedArguments` for a mix of
+/// positional and named arguments.
 pub trait PositionalArguments<'v, V>
 where
     V: 'v + FormatArgument,
@@ -139,6 +938,13 @@ where
 
     /// Creates an iterator over the arguments.
     fn iter(&'v self) -> Self::Iter;
+
+    /// Returns the number of arguments, if it's known without iterating over them. The default
+    /// implementation returns `None`, in which case a negative index can't be resolved, and
+    /// `Parser::with_strict_arguments` doesn't check for unused positional arguments.
+    fn known_len(&self) -> Option<usize> {
+        None
+    }
 }
 
 impl<'v, V, T> PositionalArguments<'v, V> for T
@@ -146,17 +952,46 @@ where
     V: 'v + FormatArgument,
     T: AsRef<[V]> + ?Sized,
 {
-    type Iter = std::slice::Iter<'v, V>;
+    type Iter = core::slice::Iter<'v, V>;
 
     fn get(&self, index: usize) -> Option<&V> {
         <[V]>::get(self.as_ref(), index)
     }
 
+    fn known_len(&self) -> Option<usize> {
+        Some(self.as_ref().len())
+    }
+
     fn iter(&'v self) -> Self::Iter {
         <[V]>::iter(self.as_ref())
     }
 }
 
+/// Positional arguments that are held by reference, such as a `Vec<&V>` assembled from values
+/// stored elsewhere. A slice of references can't implement `PositionalArguments` directly, because
+/// it would overlap with the implementation for `AsRef<[V]>`.
+#[derive(Debug, Copy, Clone)]
+pub struct BorrowedArguments<'r, V>(pub &'r [&'r V]);
+
+impl<'v, V> PositionalArguments<'v, V> for BorrowedArguments<'v, V>
+where
+    V: 'v + FormatArgument,
+{
+    type Iter = core::iter::Copied<core::slice::Iter<'v, &'v V>>;
+
+    fn get(&self, index: usize) -> Option<&V> {
+        self.0.get(index).copied()
+    }
+
+    fn iter(&'v self) -> Self::Iter {
+        self.0.iter().copied()
+    }
+
+    fn known_len(&self) -> Option<usize> {
+        Some(self.0.len())
+    }
+}
+
 /// A 'PositionalArguments` implementation that always returns `None`.
 pub struct NoPositionalArguments;
 
@@ -164,14 +999,154 @@ impl<'v, V> PositionalArguments<'v, V> for NoPositionalArguments
 where
     V: 'v + FormatArgument,
 {
-    type Iter = std::iter::Empty<&'v V>;
+    type Iter = core::iter::Empty<&'v V>;
 
     fn get(&self, _: usize) -> Option<&V> {
         None
     }
 
     fn iter(&'v self) -> Self::Iter {
-        std::iter::empty()
+        core::iter::empty()
+    }
+
+    fn known_len(&self) -> Option<usize> {
+        Some(0)
+    }
+}
+
+/// Positional arguments that are supplied one at a time by a closure, e.g. to compute the values
+/// lazily. Each `{}`, or other reference to the next argument, calls the closure once, and there
+/// are no more arguments once it returns `None`.
+///
+/// Since the arguments can't be accessed by their index, an explicit index such as `{0}` is
+/// reported as a missing argument. The closure isn't restarted for each use of the arguments, so
+/// binding a template twice continues where the first binding left off.
+pub struct NextArguments<F>(RefCell<F>);
+
+impl<F> NextArguments<F> {
+    /// Creates positional arguments that are supplied by the given closure.
+    pub fn new(next: F) -> Self {
+        NextArguments(RefCell::new(next))
+    }
+}
+
+/// An iterator over the arguments supplied by the closure of a `NextArguments`.
+pub struct NextArgumentsIter<'v, F>(&'v RefCell<F>);
+
+impl<'v, V, F> Iterator for NextArgumentsIter<'v, F>
+where
+    V: 'v,
+    F: FnMut() -> Option<&'v V>,
+{
+    type Item = &'v V;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        (self.0.borrow_mut())()
+    }
+}
+
+impl<'v, V, F> PositionalArguments<'v, V> for NextArguments<F>
+where
+    V: 'v + FormatArgument,
+    F: 'v + FnMut() -> Option<&'v V>,
+{
+    type Iter = NextArgumentsIter<'v, F>;
+
+    fn get(&self, _: usize) -> Option<&V> {
+        None
+    }
+
+    fn iter(&'v self) -> Self::Iter {
+        NextArgumentsIter(&self.0)
+    }
+}
+
+/// A single collection of arguments, some of which are named and some of which are positional.
+///
+/// Only the unnamed entries are positional arguments, indexed in the order in which they appear in
+/// the collection. The named entries don't take up positional indices, so `{0}` always refers to
+/// the first unnamed entry, regardless of any named entries that precede it. If the same name
+/// appears more than once, the first entry with that name is used.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CombinedArguments<V> {
+    entries: Vec<(Option<String>, V)>,
+    positional: Vec<usize>,
+    named: BTreeMap<String, usize>,
+}
+
+impl<V> CombinedArguments<V> {
+    /// Creates a `CombinedArguments` from entries that are optionally named.
+    pub fn new(entries: Vec<(Option<String>, V)>) -> Self {
+        let mut positional = Vec::new();
+        let mut named = BTreeMap::new();
+        for (idx, (name, _)) in entries.iter().enumerate() {
+            match name {
+                Some(name) => {
+                    named.entry(name.clone()).or_insert(idx);
+                }
+                None => positional.push(idx),
+            }
+        }
+        CombinedArguments {
+            entries,
+            positional,
+            named,
+        }
+    }
+}
+
+impl<V> FromIterator<(Option<String>, V)> for CombinedArguments<V> {
+    fn from_iter<I: IntoIterator<Item = (Option<String>, V)>>(iter: I) -> Self {
+        Self::new(iter.into_iter().collect())
+    }
+}
+
+impl<V> NamedArguments<V> for CombinedArguments<V>
+where
+    V: FormatArgument,
+{
+    fn get(&self, key: &str) -> Option<&V> {
+        self.named.get(key).map(|idx| &self.entries[*idx].1)
+    }
+
+    fn names(&self) -> Vec<&str> {
+        self.named.keys().map(String::as_str).collect()
+    }
+}
+
+/// An iterator over the positional arguments in a `CombinedArguments`.
+pub struct CombinedArgumentsIter<'v, V> {
+    entries: &'v [(Option<String>, V)],
+    positional: core::slice::Iter<'v, usize>,
+}
+
+impl<'v, V> Iterator for CombinedArgumentsIter<'v, V> {
+    type Item = &'v V;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.positional.next().map(|idx| &self.entries[*idx].1)
+    }
+}
+
+impl<'v, V> PositionalArguments<'v, V> for CombinedArguments<V>
+where
+    V: 'v + FormatArgument,
+{
+    type Iter = CombinedArgumentsIter<'v, V>;
+
+    fn get(&self, index: usize) -> Option<&V> {
+        self.positional.get(index).map(|idx| &self.entries[*idx].1)
+    }
+
+    fn iter(&'v self) -> Self::Iter {
+        CombinedArgumentsIter {
+            entries: &self.entries,
+            positional: self.positional.iter(),
+        }
+    }
+
+    fn known_len(&self) -> Option<usize> {
+        Some(self.positional.len())
     }
 }
 
@@ -191,4 +1166,226 @@ where
 
     /// Returns the named argument with the given name, if any.
     fn lookup_argument_by_name(&self, name: &str) -> Option<&V>;
+
+    /// Returns the number of positional arguments, if it's known. The parser uses it to resolve a
+    /// negative index, and to report how many arguments there are when one is missing. The default
+    /// implementation returns `None`, in which case a negative index can't be resolved, and the
+    /// parser only looks up the arguments before the missing one to report how many there are.
+    fn positional_len(&self) -> Option<usize> {
+        None
+    }
+}
+
+/// An argument source that delegates to another one and counts how many times each argument is
+/// read, e.g. to find the arguments that a formatting string never uses.
+///
+/// Only the lookups that find a value are counted. The `n`-th argument returned by `next_argument`
+/// is counted as the positional argument with index `n - 1`, regardless of any lookups by index.
+/// When a positional argument is missing and the wrapped source doesn't know how many there are,
+/// the parser reads the ones before it to report that, so the counts after such an error include
+/// those reads.
+pub struct Counting<S> {
+    inner: S,
+    next_index: usize,
+    positional: RefCell<BTreeMap<usize, usize>>,
+    named: RefCell<BTreeMap<String, usize>>,
+}
+
+impl<S> Counting<S> {
+    /// Creates a source that counts the reads of the arguments from the given source.
+    pub fn new(inner: S) -> Self {
+        Counting {
+            inner,
+            next_index: 0,
+            positional: RefCell::new(BTreeMap::new()),
+            named: RefCell::new(BTreeMap::new()),
+        }
+    }
+
+    /// Returns how many times the positional argument with the given index was read.
+    pub fn count_for_index(&self, idx: usize) -> usize {
+        self.positional.borrow().get(&idx).copied().unwrap_or(0)
+    }
+
+    /// Returns how many times the named argument with the given name was read.
+    pub fn count_for_name(&self, name: &str) -> usize {
+        self.named.borrow().get(name).copied().unwrap_or(0)
+    }
+
+    /// Returns the read counts of the positional arguments that were read at least once, by index.
+    pub fn positional_counts(&self) -> BTreeMap<usize, usize> {
+        self.positional.borrow().clone()
+    }
+
+    /// Returns the read counts of the named arguments that were read at least once, by name.
+    pub fn named_counts(&self) -> BTreeMap<String, usize> {
+        self.named.borrow().clone()
+    }
+
+    /// Returns the wrapped source.
+    pub fn into_inner(self) -> S {
+        self.inner
+    }
+}
+
+impl<V, S> ArgumentSource<V> for Counting<S>
+where
+    V: FormatArgument,
+    S: ArgumentSource<V>,
+{
+    fn next_argument(&mut self) -> Option<&V> {
+        let idx = self.next_index;
+        self.next_index += 1;
+        let value = self.inner.next_argument();
+        if value.is_some() {
+            *self.positional.borrow_mut().entry(idx).or_insert(0) += 1;
+        }
+        value
+    }
+
+    fn lookup_argument_by_index(&self, idx: usize) -> Option<&V> {
+        let value = self.inner.lookup_argument_by_index(idx);
+        if value.is_some() {
+            *self.positional.borrow_mut().entry(idx).or_insert(0) += 1;
+        }
+        value
+    }
+
+    fn lookup_argument_by_name(&self, name: &str) -> Option<&V> {
+        let value = self.inner.lookup_argument_by_name(name);
+        if value.is_some() {
+            *self.named.borrow_mut().entry(name.to_string()).or_insert(0) += 1;
+        }
+        value
+    }
+
+    fn positional_len(&self) -> Option<usize> {
+        self.inner.positional_len()
+    }
+}
+
+/// Formats the number in a `JsonValue` with the given `std::fmt` trait. Integers are formatted as
+/// `u64` or `i64`, and other numbers as `f64` if `float` is given, or not at all otherwise.
+#[cfg(feature = "serde_json")]
+macro_rules! fmt_json_number {
+    ($value:expr, $f:expr, $fmt_trait:ident $(, $float:ident)?) => {
+        match $value {
+            serde_json::Value::Number(number) => {
+                if let Some(val) = number.as_u64() {
+                    fmt::$fmt_trait::fmt(&val, $f)
+                } else if let Some(val) = number.as_i64() {
+                    fmt::$fmt_trait::fmt(&val, $f)
+                } else {
+                    fmt_json_number!(@float number, $f, $fmt_trait $(, $float)?)
+                }
+            }
+            _ => Err(fmt::Error),
+        }
+    };
+    (@float $number:expr, $f:expr, $fmt_trait:ident, float) => {
+        match $number.as_f64() {
+            Some(val) => fmt::$fmt_trait::fmt(&val, $f),
+            None => Err(fmt::Error),
+        }
+    };
+    (@float $number:expr, $f:expr, $fmt_trait:ident) => {
+        Err(fmt::Error)
+    };
+}
+
+/// Wraps a JSON value from `serde_json`, so that deserialized data can be formatted directly.
+///
+/// Numbers support all the formats except the pointer format, although the octal, hexadecimal
+/// and binary formats are only supported by integers. Numbers can also supply the width or
+/// precision, if they are non-negative integers. Strings support the display and debug formats,
+/// and booleans and null support only the display format. Arrays and objects support only the
+/// debug format, which writes them as JSON, pretty-printed with `{:#?}`. Like in `format!`,
+/// numbers are aligned to the right by default, and everything else to the left.
+#[cfg(feature = "serde_json")]
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct JsonValue<'a>(pub &'a serde_json::Value);
+
+#[cfg(feature = "serde_json")]
+impl<'a> FormatArgument for JsonValue<'a> {
+    fn supports_format(&self, specifier: &Specifier) -> bool {
+        use serde_json::Value;
+        match (self.0, specifier.format) {
+            (Value::Number(number), Format::Octal)
+            | (Value::Number(number), Format::LowerHex)
+            | (Value::Number(number), Format::UpperHex)
+            | (Value::Number(number), Format::Binary) => !number.is_f64(),
+            (Value::Number(_), Format::Display)
+            | (Value::Number(_), Format::Debug)
+            | (Value::Number(_), Format::LowerExp)
+            | (Value::Number(_), Format::UpperExp) => true,
+            (Value::String(_), Format::Display) | (Value::String(_), Format::Debug) => true,
+            (Value::Bool(_), Format::Display) | (Value::Null, Format::Display) => true,
+            (Value::Array(_), Format::Debug) | (Value::Object(_), Format::Debug) => true,
+            _ => false,
+        }
+    }
+
+    fn fmt_display(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use serde_json::Value;
+        match self.0 {
+            Value::String(val) => fmt::Display::fmt(val, f),
+            Value::Bool(val) => fmt::Display::fmt(val, f),
+            Value::Null => f.pad("null"),
+            value => fmt_json_number!(value, f, Display, float),
+        }
+    }
+
+    fn fmt_debug(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use serde_json::Value;
+        match self.0 {
+            Value::String(val) => fmt::Debug::fmt(val, f),
+            value @ Value::Array(_) | value @ Value::Object(_) => {
+                let json = if f.alternate() {
+                    serde_json::to_string_pretty(value)
+                } else {
+                    serde_json::to_string(value)
+                };
+                f.pad(&json.map_err(|_| fmt::Error)?)
+            }
+            value => fmt_json_number!(value, f, Debug, float),
+        }
+    }
+
+    fn fmt_octal(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt_json_number!(self.0, f, Octal)
+    }
+
+    fn fmt_lower_hex(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt_json_number!(self.0, f, LowerHex)
+    }
+
+    fn fmt_upper_hex(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt_json_number!(self.0, f, UpperHex)
+    }
+
+    fn fmt_binary(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt_json_number!(self.0, f, Binary)
+    }
+
+    fn fmt_lower_exp(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt_json_number!(self.0, f, LowerExp, float)
+    }
+
+    fn fmt_upper_exp(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt_json_number!(self.0, f, UpperExp, float)
+    }
+
+    fn natural_alignment(&self) -> Align {
+        match self.0 {
+            serde_json::Value::Number(_) => Align::Right,
+            _ => Align::Left,
+        }
+    }
+
+    fn to_usize(&self) -> Result<usize, ()> {
+        match self.0.as_u64() {
+            Some(val) => usize::try_from(val).map_err(|_| ()),
+            None => Err(()),
+        }
+    }
 }
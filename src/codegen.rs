@@ -1,4 +1,4 @@
-//! The `generate_code!` macro generates the `Specifier` struct, `format_value` function, and all
+//! The `generate_code!` macro generates the `Specifier` struct, `format_std` function, and all
 //! the code they need.
 //! 
 //! The macro expects definitions of "dimensions" of the format specifier (e.g. width, precision,
@@ -8,12 +8,37 @@
 //! one or more fields for that variant to contain, and then the format string fragment to generate
 //! when that variant is matched.
 //! 
-//! The way `format_value` function works is through a tree of nested `match` blocks on `Specifier`
+//! If none of the variants of a dimension have fields, the enum also gets an `ALL` constant that
+//! lists the variants in order, and a `symbol` method that returns the format string fragment of a
+//! variant.
+//! 
+//! A variant definition can end with `by_hand`. `format_std` returns an error for such a variant,
+//! so that the value doesn't need the `std::fmt` trait the variant would be formatted with, and the
+//! caller has to format it some other way. The variant is otherwise generated like the others.
+//! 
+//! The variant definitions of a dimension can be followed by `;` and a list of extra variants.
+//! These are added to the enum as written, but `format_std` returns an error for them, and the enum
+//! doesn't get the generated `TryFrom<&str>` and `Display` impls or the `symbol` method, so they
+//! have to be written by hand. Instead, it gets a `standard_symbol` method, which returns the
+//! format string fragment of a variant that isn't extra, and a `from_standard_symbol` function that
+//! does the opposite, so that only the extra variants need to be handled by hand. The extra
+//! variants are left out of `ALL`.
+//! 
+//! The dimensions can be preceded by a braced list of plain fields. These are added to the
+//! `Specifier` struct and initialized with their `Default` values, but `format_std` ignores them.
+//! They are meant for the parts of the specification that can't be expressed with a `write!`
+//! formatting string literal.
+//! 
+//! The way `format_std` function works is through a tree of nested `match` blocks on `Specifier`
 //! fields, with a call to `write!` macro with a different formatting string at each leaf.
 //! 
 //! # Examples
 //! ```ignore
 //! generate_code! {
+//!     {
+//!         qux: Option<char>,
+//!     }
+//! 
 //!     foo: Foo {
 //!         Argle => "",
 //!         Bargle { glop_glyf: usize } => "glop_glyf$",
@@ -26,9 +51,9 @@
 //! }
 //! ```
 //! 
-//! The resulting `format_value` would look like this:
+//! The resulting `format_std` would look like this:
 //! ```ignore
-//! pub fn format_value<V>(specifier: &Specifier, value: &V, f: &mut fmt::Formatter) -> fmt::Result {
+//! fn format_std<V>(specifier: &Specifier, value: &V, f: &mut fmt::Formatter) -> fmt::Result {
 //!     match (specifier.foo) {
 //!         Argle => match (specifier.bar) {
 //!             Olle => write!(f, "{:}", value),
@@ -43,31 +68,47 @@
 
 macro_rules! generate_code {
     {
+        $({
+            $(
+                $(#[$plain_meta:meta])*
+                $plain_field:ident : $plain_type:ty
+            ),* $(,)?
+        })?
         $(
             $(#[$dim_meta:meta])*
             $field:ident : $type:ident {
                 $(
                     $variant:ident $({ $($var_field:ident : $var_type:ty),+ })? => $lit:literal
+                    $($by_hand:ident)?
                 ),+ $(,)? 
+                $(; $($extra:tt)*)?
             }
         )+
     } => {
         $(
             $(#[$dim_meta])*
             #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+            #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
             #[allow(missing_docs)]
             pub enum $type {
                 $(
-                    $variant $({ $($var_field: $var_type),+ })?
-                ),+
+                    $variant $({ $($var_field: $var_type),+ })?,
+                )+
+                $($($extra)*)?
             }
-            generate_code!(@enum_try_from $type [] [$(($lit $variant $({$($var_field)+})?))+]);
-            generate_code!(@enum_display $type [] [$(($lit $variant $({$($var_field)+})?))+]);
+            generate_code!(
+                @enum_impls $type [$(($lit $variant $({$($var_field)+})?))+] [$($($extra)*)?]
+            );
         )+
 
         /// The specification for the format of an argument in the formatting string.
         #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+        #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
         pub struct Specifier {
+            $($(
+                $(#[$plain_meta])*
+                pub $plain_field: $plain_type,
+            )*)?
             $(
                 $(#[$dim_meta])*
                 pub $field: $type
@@ -76,31 +117,32 @@ macro_rules! generate_code {
 
         generate_code!(@fn_format_value
             $(
-                [$field $type $([$lit $variant $([$($var_field)+])?])+]
+                [$field $type $([$lit $variant ($($by_hand)?) $([$($var_field)+])?])+]
             )+
         );
 
         impl Default for Specifier {
             fn default() -> Self {
                 Self {
+                    $($(
+                        $plain_field: Default::default(),
+                    )*)?
                     $(
                         $field: generate_code!(@first_variant $type $($variant)+)
                     ),+
                 }
             }
         }
-
-        impl fmt::Display for Specifier {
-            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-                write!(
-                    f,
-                    concat!($("{", stringify!($field), "}"),+),
-                    $(
-                        $field=self.$field
-                    ),+
-                )
-            }
-        }
+    };
+    (@enum_impls $type:ident [$($variants:tt)+] []) => {
+        generate_code!(@enum_try_from $type [] [$($variants)+]);
+        generate_code!(@enum_display $type [] [$($variants)+]);
+        generate_code!(@enum_all $type [] [$($variants)+]);
+        generate_code!(@enum_symbol $type [] [$($variants)+]);
+    };
+    (@enum_impls $type:ident [$($variants:tt)+] [$($extra:tt)+]) => {
+        generate_code!(@enum_all $type [] [$($variants)+]);
+        generate_code!(@enum_standard_symbol $type [] [$($variants)+]);
     };
     (@enum_try_from
         $type:ident [$($munched:tt)*] [($lit:literal $variant:ident) $($tail:tt)*]
@@ -144,24 +186,86 @@ macro_rules! generate_code {
             }
         }
     };
+    (@enum_all
+        $type:ident [$($munched:tt)*] [($lit:literal $variant:ident) $($tail:tt)*]
+    ) => {
+        generate_code!(@enum_all $type [$($munched)* ($lit $variant)] [$($tail)*]);
+    };
+    (@enum_all
+        $type:ident [$($munched:tt)*] [($lit:literal $variant:ident $_:tt) $($tail:tt)*]
+    ) => {
+    };
+    (@enum_all
+        $type:ident [$(($lit:literal $variant:ident))+] []
+    ) => {
+        impl $type {
+            /// The variants that don't hold any data, in the order they're declared in.
+            pub const ALL: &'static [$type] = &[$($type::$variant),+];
+        }
+    };
+    (@enum_symbol
+        $type:ident [$($munched:tt)*] [($lit:literal $variant:ident) $($tail:tt)*]
+    ) => {
+        generate_code!(@enum_symbol $type [$($munched)* ($lit $variant)] [$($tail)*]);
+    };
+    (@enum_symbol
+        $type:ident [$($munched:tt)*] [($lit:literal $variant:ident $_:tt) $($tail:tt)*]
+    ) => {
+    };
+    (@enum_symbol
+        $type:ident [$(($lit:literal $variant:ident))+] []
+    ) => {
+        impl $type {
+            /// Returns the fragment of a format specifier that selects this variant, which is also
+            /// how it's displayed.
+            pub fn symbol(&self) -> &'static str {
+                match self {
+                    $($type::$variant => $lit,)+
+                }
+            }
+        }
+    };
+    (@enum_standard_symbol
+        $type:ident [$($munched:tt)*] [($lit:literal $variant:ident) $($tail:tt)*]
+    ) => {
+        generate_code!(@enum_standard_symbol $type [$($munched)* ($lit $variant)] [$($tail)*]);
+    };
+    (@enum_standard_symbol
+        $type:ident [$($munched:tt)*] [($lit:literal $variant:ident $_:tt) $($tail:tt)*]
+    ) => {
+    };
+    (@enum_standard_symbol
+        $type:ident [$(($lit:literal $variant:ident))+] []
+    ) => {
+        impl $type {
+            /// Returns the fragment of a format specifier that selects this variant, or `None` for
+            /// an extra variant.
+            fn standard_symbol(&self) -> Option<&'static str> {
+                match self {
+                    $($type::$variant => Some($lit),)+
+                    _ => None,
+                }
+            }
+
+            /// Returns the variant, other than an extra one, that the given fragment of a format
+            /// specifier selects, if any.
+            fn from_standard_symbol(symbol: &str) -> Option<Self> {
+                match symbol {
+                    $($lit => Some($type::$variant),)+
+                    _ => None,
+                }
+            }
+        }
+    };
     (@first_variant $type:ident $first:ident $($rest:ident)*) => { $type::$first };
     (@fn_format_value $($dim:tt)+) => {
-        /// Formats the given value using the given formatter and the given format specification.
-        /// 
-        /// Since the implementation of `format_value` employs the `write!` macro, the `value` must
-        /// implement all of the `std::fmt` formatting traits. Which trait will actually be used is
-        /// determined at runtime, based on the contents of the `specifier`.
-        pub fn format_value<V>(specifier: &Specifier, value: &V, f: &mut fmt::Formatter) -> fmt::Result
-        where
-            V: fmt::Display
-                + fmt::Debug
-                + fmt::Octal
-                + fmt::LowerHex
-                + fmt::UpperHex
-                + fmt::Binary
-                + fmt::LowerExp
-                + fmt::UpperExp,
-        {
+        /// Formats the given value using the given formatter and the dimensions of the given format
+        /// specification. Plain fields of the specification are ignored.
+        fn format_std<V: StdFormat>(
+            specifier: &Specifier,
+            value: &V,
+            f: &mut fmt::Formatter,
+        ) -> fmt::Result {
             generate_code!(@matcher (specifier, value, f, "", []) $($dim)+)
         }
     };
@@ -173,19 +277,25 @@ macro_rules! generate_code {
     };
     (@matcher_branch
         ($spec:ident, $val:ident, $out:ident, $prefix:expr, $named_args:tt)
-        [$field:ident $type:ident $([$lit:literal $variant:ident $([$($var_field:ident)+])?])+]
+        [$field:ident $type:ident $([
+            $lit:literal $variant:ident ($($by_hand:ident)?) $([$($var_field:ident)+])?
+        ])+]
         $tail:tt
     ) => {
         match $spec.$field {
             $(
                 $type::$variant $({ $($var_field),+ })? => generate_code!(
-                    @matcher_tail
-                    ($spec, $val, $out, concat!($prefix, $lit))
-                    $named_args
-                    [$($($var_field)+)?]
-                    $tail
-                )
-            ),+
+                    @unless_by_hand ($($by_hand)?) generate_code!(
+                        @matcher_tail
+                        ($spec, $val, $out, concat!($prefix, $lit))
+                        $named_args
+                        [$($($var_field)+)?]
+                        $tail
+                    )
+                ),
+            )+
+            #[allow(unreachable_patterns)]
+            _ => Err(fmt::Error),
         }
     };
     (@matcher_tail ($spec:ident, $val:ident, $out:ident, $prefix:expr) [$($lhs_arg:ident)*] [$($rhs_arg:ident)*] [$($dim:tt)+]) => {
@@ -193,19 +303,27 @@ macro_rules! generate_code {
     };
     (@matcher_leaf
         ($spec:ident, $val:ident, $out:ident, $prefix:expr, $named_args:tt)
-        [$field:ident $type:ident $([$lit:literal $variant:ident $([$($var_field:ident)+])?])+]
+        [$field:ident $type:ident $([
+            $lit:literal $variant:ident ($($by_hand:ident)?) $([$($var_field:ident)+])?
+        ])+]
     ) => {
         match $spec.$field {
             $(
                 $type::$variant $({ $($var_field),+ })? => generate_code!(
-                    @matcher_concat_args
-                    ($spec, $val, $out, concat!($prefix, $lit))
-                    $named_args
-                    [$($($var_field)+)?]
-                )
-            ),+
+                    @unless_by_hand ($($by_hand)?) generate_code!(
+                        @matcher_concat_args
+                        ($spec, $val, $out, concat!($prefix, $lit))
+                        $named_args
+                        [$($($var_field)+)?]
+                    )
+                ),
+            )+
+            #[allow(unreachable_patterns)]
+            _ => Err(fmt::Error),
         }
     };
+    (@unless_by_hand () $($arm:tt)+) => { $($arm)+ };
+    (@unless_by_hand (by_hand) $($arm:tt)+) => { Err(fmt::Error) };
     (@matcher_concat_args ($spec:ident, $val:ident, $out:ident, $format_str:expr) [$($lhs_arg:ident)*] [$($rhs_arg:ident)*]) => {
         generate_code!(@matcher_write ($spec, $val, $out, $format_str) [$($lhs_arg)* $($rhs_arg)*])
     };
@@ -213,7 +331,7 @@ macro_rules! generate_code {
         write!(
             $out,
             concat!("{:", $format_str, "}"),
-            $val,
+            *$val,
             $($named_arg = $named_arg),*
         )
     };
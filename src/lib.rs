@@ -1,15 +1,15 @@
 #![warn(missing_docs)]
+#![cfg_attr(not(feature = "std"), no_std)]
 
 //! Fully-runtime equivalent of the `format!` macro.
 //! 
 //! Allows formatting strings like the `format!` macro, with the formatting string and the arguments
-//! provided at runtime. This crate supports all the formatting features of the `format!` macro,
-//! except for the fill character.
+//! provided at runtime. This crate supports all the formatting features of the `format!` macro.
 //! 
 //! # Examples
 //! 
 //! ```
-//! use rt_format::{Format, FormatArgument, ParsedFormat, Specifier};
+//! use rt_format::{Align, Format, FormatArgument, ParsedFormat, Specifier};
 //! use std::cmp::PartialEq;
 //! use std::fmt;
 //!
@@ -22,11 +22,11 @@
 //! impl FormatArgument for Variant {
 //!     fn supports_format(&self, spec: &Specifier) -> bool {
 //!         match self {
-//!             Self::Int(_) => true,
-//!             Self::Float(_) => match spec.format {
-//!                 Format::Display | Format::Debug | Format::LowerExp | Format::UpperExp => true,
-//!                 _ => false,
-//!             },
+//!             Self::Int(_) => !matches!(spec.format, Format::Pointer | Format::Custom(_)),
+//!             Self::Float(_) => matches!(
+//!                 spec.format,
+//!                 Format::Display | Format::Debug | Format::LowerExp | Format::UpperExp
+//!             ),
 //!         }
 //!     }
 //! 
@@ -83,16 +83,23 @@
 //!         }
 //!     }
 //! 
+//!     // Aligns numbers to the right when no alignment is given, like `format!`.
+//!     fn natural_alignment(&self) -> Align {
+//!         Align::Right
+//!     }
+//! 
+//!     // Lets an `Int` supply the width or precision, as in `{:1$}` or `{:.*}`.
 //!     fn to_usize(&self) -> Result<usize, ()> {
-//!         use std::convert::TryInto;
+//!         use std::convert::TryFrom;
 //!         match self {
-//!             Variant::Int(val) => (*val).try_into().map_err(|_| ()),
+//!             Variant::Int(val) => usize::try_from(*val).map_err(|_| ()),
 //!             Variant::Float(_) => Err(()),
 //!         }
 //!     }
 //! }
 //! 
 //! fn main() {
+//!     # #[cfg(feature = "std")] {
 //!     use std::collections::HashMap;
 //! 
 //!     let pos_args = [Variant::Int(42), Variant::Int(5)];
@@ -100,25 +107,99 @@
 //!     let mut named_args = HashMap::new();
 //!     named_args.insert("foo".to_string(), Variant::Float(42.042));
 //! 
-//!     let args = ParsedFormat::parse("{:#x} [{0:<5}] {foo:.1$}", &pos_args, &named_args).unwrap();
-//!     assert_eq!("0x2a [42   ] 42.04200", format!("{}", args));
+//!     let spec = "{:#x} [{0:<5}] [{0:5}] {foo:.1$}";
+//!     let args = ParsedFormat::parse(spec, &pos_args, &named_args).unwrap();
+//!     assert_eq!("0x2a [42   ] [   42] 42.04200", format!("{}", args));
+//!     # }
 //! }
 //! ```
+//! 
+//! For the common case of formatting numbers and strings, `prelude::Primitive` is a ready-made
+//! argument type that wraps any primitive value, so no `FormatArgument` impl is needed.
+//! 
+//! With the `derive` feature enabled, `FormatArgument` can also be derived for enums like `Variant`
+//! above, whose variants each wrap a single value. See the documentation of the derive macro for
+//! the attributes that control it.
+//! 
+//! With the `serde` feature enabled, `Specifier` and the enums that make up its fields implement
+//! `Serialize` and `Deserialize`.
+//! 
+//! With the `serde_json` feature enabled, `argument::JsonValue` makes a `serde_json::Value`
+//! usable as an argument.
+//! 
+//! With the `log` feature enabled, the `rt_log!` macro logs a message with a runtime formatting
+//! string through the `log` crate.
+//! 
+//! With the `unicode-width` feature enabled, `Measure::Columns` is available, which measures the
+//! width and the maximum width in the columns the text takes up in a terminal, instead of in
+//! `char`s. It only applies where it's asked for, with `Parser::with_measure`,
+//! `Template::with_measure` or the `measure` of a `Specifier`, so enabling the feature doesn't
+//! change any output by itself.
+//! 
+//! With the `index-or-name` feature enabled, a substitution with an index can name an argument to
+//! use if there's no positional argument with that index, as in `{0|legacy_name}` or
+//! `{0|legacy_name:>8}`. This is meant for migrating formatting strings from one set of arguments
+//! to another, so it's not part of the standard grammar.
+//! 
+//! The `std` feature is enabled by default. Without it, the crate is `no_std` and only needs
+//! `alloc`. The following APIs are only available with `std`:
+//! * `NamedArguments` for `HashMap`. Use `BTreeMap`, slices of pairs, or `CombinedArguments`
+//!   instead.
+//! * `ParsedFormat::write_io`.
+//! * The conversion of a `SystemTime` to an `argument::DurationValue`.
+//! * The `std::error::Error` impls for `ParseError` and `FormatError`.
+
+extern crate alloc;
 
 #[macro_use]
 mod codegen;
 
 pub mod argument;
 pub mod parser;
+pub mod prelude;
 
-use std::cmp::PartialEq;
-use std::convert::TryFrom;
-use std::fmt;
+use alloc::borrow::Cow;
+use alloc::string::{String, ToString};
+use core::cmp::PartialEq;
+use core::convert::TryFrom;
+use core::fmt::{self, Write};
 
-pub use crate::argument::{FormatArgument, NoNamedArguments, NoPositionalArguments};
-pub use crate::parser::{ParsedFormat, Substitution};
+use crate::argument::{NamedArguments, PositionalArguments};
+use crate::parser::Parser;
+
+pub use crate::argument::{
+    format_one, FormatArgument, NoNamedArguments, NoPositionalArguments, StdValue
+};
+#[cfg(feature = "derive")]
+pub use rt_format_derive::FormatArgument;
+#[cfg(feature = "log")]
+#[doc(hidden)]
+pub use log as __log;
+pub use crate::parser::{
+    ArgumentRef, FormatError, FormatLimits, OwnedFormat, OwnedSegment, OwnedTemplate, ParseError,
+    ParseErrorKind, ParsedFormat, Substitution, Template, TemplateVisitor
+};
 
 generate_code! {
+    {
+        /// The character used to pad an argument to a specific width, if other than a space. Only
+        /// has an effect when the alignment is given explicitly.
+        fill: Option<char>,
+        /// The separator to insert between groups of digits. Not supported by `std::fmt`.
+        grouping: Grouping,
+        /// The maximum width of the output, beyond which it is truncated. Not supported by
+        /// `std::fmt`.
+        max_width: MaxWidth,
+        /// The separators to use for a number in the display format. Not supported by `std::fmt`,
+        /// and can't be written in a formatting string.
+        #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "Locale::is_c"))]
+        locale: Locale,
+        /// How the width and the maximum width are measured. Not supported by `std::fmt`, and
+        /// can't be written in a formatting string.
+        #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "Measure::is_chars"))]
+        measure: Measure,
+    }
+
     /// Specifies the alignment of an argument with a specific width.
     align: Align {
         None => "",
@@ -168,6 +249,250 @@ generate_code! {
         Binary => "b",
         LowerExp => "e",
         UpperExp => "E",
+        Pointer => "p" by_hand;
+        /// A format that `std::fmt` doesn't know about, identified by its letter. The argument
+        /// formats it in `FormatArgument::fmt_custom`.
+        Custom(char),
+    }
+}
+
+/// Parses the fragment of a format specifier that selects a format. Any other ASCII letter selects
+/// a custom format.
+impl TryFrom<&str> for Format {
+    type Error = ();
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        match Format::from_standard_symbol(value) {
+            Some(format) => Ok(format),
+            None => {
+                let mut chars = value.chars();
+                match (chars.next(), chars.next()) {
+                    (Some(tag), None) if tag.is_ascii_alphabetic() => Ok(Format::Custom(tag)),
+                    _ => Err(()),
+                }
+            }
+        }
+    }
+}
+
+impl Format {
+    /// Returns the fragment of a format specifier that selects this format, which is also how it's
+    /// displayed. The symbol of a custom format is its character, which is owned, unlike the
+    /// symbols of the other formats. Only an ASCII letter can be parsed back, though.
+    pub fn symbol(&self) -> Cow<'static, str> {
+        match self {
+            Format::Custom(tag) => Cow::Owned(tag.to_string()),
+            format => Cow::Borrowed(format.standard_symbol().unwrap_or_default()),
+        }
+    }
+}
+
+impl fmt::Display for Format {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Format::Custom(tag) => f.write_char(*tag),
+            format => f.write_str(format.standard_symbol().unwrap_or_default()),
+        }
+    }
+}
+
+/// Specifies whether to separate groups of digits, e.g. `1,234,567`.
+///
+/// Applies to the display, octal, hexadecimal and binary formats. Decimal digits are grouped by
+/// three, and the other radixes by four. Only the integer part of the output is grouped, after the
+/// sign and the alternate-form prefix, and the grouping is applied before padding to the width.
+/// Arguments that aren't numbers are never grouped, see `FormatArgument::is_numeric`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Grouping {
+    /// Digits are not grouped.
+    #[default]
+    None,
+    /// Groups are separated with `,`.
+    Comma,
+    /// Groups are separated with `_`.
+    Underscore,
+    /// Groups are separated with a space.
+    Space,
+}
+
+impl Grouping {
+    /// All the variants, in the order they're declared in.
+    pub const ALL: &'static [Grouping] =
+        &[Grouping::None, Grouping::Comma, Grouping::Underscore, Grouping::Space];
+
+    /// Returns the fragment of a format specifier that selects this grouping, which is also how
+    /// it's displayed.
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            Grouping::None => "",
+            Grouping::Comma => ",",
+            Grouping::Underscore => "_",
+            Grouping::Space => " ",
+        }
+    }
+
+    fn separator(self) -> Option<char> {
+        match self {
+            Grouping::None => None,
+            Grouping::Comma => Some(','),
+            Grouping::Underscore => Some('_'),
+            Grouping::Space => Some(' '),
+        }
+    }
+}
+
+impl TryFrom<&str> for Grouping {
+    type Error = ();
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        match value {
+            "" => Ok(Grouping::None),
+            "," => Ok(Grouping::Comma),
+            "_" => Ok(Grouping::Underscore),
+            " " => Ok(Grouping::Space),
+            _ => Err(()),
+        }
+    }
+}
+
+impl fmt::Display for Grouping {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.separator() {
+            Some(separator) => f.write_char(separator),
+            None => Ok(()),
+        }
+    }
+}
+
+/// The separators to use when formatting a number with the display format, e.g. `1.234,56`.
+///
+/// A locale other than `Locale::C` only affects numeric arguments, see
+/// `FormatArgument::is_numeric`, and only their output that looks like a decimal number: an
+/// optional sign, then digits, optionally followed by a decimal point and more digits. The decimal
+/// point is replaced with `decimal_separator`, and the digits before it are separated into groups
+/// of `grouping_size` with `grouping_separator`, if any. A grouping in the specifier takes
+/// precedence over the one of the locale. Other output, such as `NaN` or text, is left alone.
+///
+/// # Examples
+/// ```
+/// use rt_format::prelude::*;
+/// use rt_format::Locale;
+///
+/// let german = Locale {
+///     decimal_separator: ',',
+///     grouping_separator: Some('.'),
+///     grouping_size: 3,
+/// };
+/// let args: [Primitive; 1] = [1234.5.into()];
+/// let parser = Parser::new("{:.2} {0:>10}", &args, &NoNamedArguments).with_locale(german);
+/// let parsed = ParsedFormat::from_parser(parser).unwrap();
+/// assert_eq!("1.234,50    1.234,5", parsed.to_string());
+/// ```
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Locale {
+    /// The character that separates the integer part from the fractional part.
+    pub decimal_separator: char,
+    /// The character that separates groups of digits in the integer part, if they're grouped.
+    pub grouping_separator: Option<char>,
+    /// The number of digits in a group. Zero means that the digits aren't grouped.
+    pub grouping_size: usize,
+}
+
+impl Locale {
+    /// The locale that `std::fmt` uses: `.` as the decimal point, and no grouping.
+    pub const C: Locale = Locale {
+        decimal_separator: '.',
+        grouping_separator: None,
+        grouping_size: 3,
+    };
+
+    #[cfg(feature = "serde")]
+    fn is_c(&self) -> bool {
+        *self == Locale::C
+    }
+}
+
+impl Default for Locale {
+    fn default() -> Self {
+        Locale::C
+    }
+}
+
+/// Specifies how the width and the maximum width are measured when padding and truncating.
+///
+/// The default is to count `char`s, like `std::fmt` does. Other measures are only available with
+/// the cargo features that provide them, so the enum is `#[non_exhaustive]`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub enum Measure {
+    /// Every `char` counts as one.
+    #[default]
+    Chars,
+    /// Every `char` counts as the number of columns it takes up in a terminal, according to the
+    /// `unicode-width` crate, so that wide characters, such as most CJK characters, and combining
+    /// marks line up in a table. Only available with the `unicode-width` feature.
+    ///
+    /// Since `std::fmt` always measures in `char`s, the output is padded by this crate whenever the
+    /// alignment is known, i.e. when it's given in the specifier or by the natural alignment of the
+    /// argument, and the output isn't padded with zeroes. Without a fill character, the debug
+    /// format is still padded by `std::fmt`, since it passes the width on to the fields of a value.
+    #[cfg(feature = "unicode-width")]
+    Columns,
+}
+
+impl Measure {
+    #[cfg(feature = "serde")]
+    fn is_chars(&self) -> bool {
+        *self == Measure::Chars
+    }
+
+    /// Returns the width of the character, as counted by this measure.
+    fn char_width(self, c: char) -> usize {
+        match (self, c) {
+            (Measure::Chars, _) => 1,
+            #[cfg(feature = "unicode-width")]
+            (Measure::Columns, c) => unicode_width::UnicodeWidthChar::width(c).unwrap_or(0),
+        }
+    }
+
+    /// Returns the width of the text, as counted by this measure.
+    fn text_width(self, text: &str) -> usize {
+        text.chars().map(|c| self.char_width(c)).sum()
+    }
+}
+
+/// Specifies whether the output should be truncated to a maximum width.
+///
+/// Unlike the precision, which only truncates strings, the maximum width applies to the output of
+/// any format. The width is measured as given by the `Measure` of the specifier, and truncation
+/// never splits a character. The output is truncated before it's padded to the minimum width, if
+/// any.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum MaxWidth {
+    /// The output is never truncated.
+    #[default]
+    Unlimited,
+    /// Longer output is cut off after `width` characters.
+    Cut {
+        /// The maximum number of characters.
+        width: usize,
+    },
+    /// Longer output is cut off, and `…` is appended, so that it has `width` characters.
+    Ellipsis {
+        /// The maximum number of characters, including the ellipsis.
+        width: usize,
+    },
+}
+
+impl fmt::Display for MaxWidth {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MaxWidth::Unlimited => Ok(()),
+            MaxWidth::Cut { width } => write!(f, "~{}", width),
+            MaxWidth::Ellipsis { width } => write!(f, "…{}", width),
+        }
     }
 }
 
@@ -188,3 +513,728 @@ impl fmt::Display for Precision {
         }
     }
 }
+
+/// Writes the specifier the way it's written in a formatting string, without the leading `:`.
+///
+/// The parts are written in the only order that the parser accepts, which is also the order of
+/// `format!`: the fill and alignment, `+`, `#`, `0`, the width, the grouping, the maximum width,
+/// the precision and the format. Flags in any other order, such as `#+`, are an invalid specifier.
+///
+/// Parsing the output gives back the same specifier, with a few exceptions that can't be written
+/// any other way: a fill is only written along with an alignment, a zero width without zero padding
+/// reads back as zero padding, and a custom format whose letter is one of the standard ones reads
+/// back as the standard format. The locale and the measure aren't written at all.
+impl fmt::Display for Specifier {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.write_with_refs(f, None, None)
+    }
+}
+
+impl Specifier {
+    /// Creates a builder that starts from the default specification.
+    pub fn builder() -> SpecifierBuilder {
+        SpecifierBuilder::default()
+    }
+
+    /// Writes a substitution for the given argument with this specifier, as it would appear in a
+    /// formatting string. The width and precision are taken from the given arguments, if any, as
+    /// in `w$` or `.*`, instead of this specifier. The `:` is left out if there's nothing to write
+    /// after it.
+    ///
+    /// # Examples
+    /// ```
+    /// use rt_format::{ArgumentRef, Specifier};
+    ///
+    /// let spec: Specifier = ">8.2".parse().unwrap();
+    /// let foo = ArgumentRef::Named("foo".to_string());
+    /// assert_eq!("{foo:>8.2}", spec.placeholder_for(&foo, None, None));
+    /// let width = ArgumentRef::Named("w".to_string());
+    /// let next = ArgumentRef::Next;
+    /// assert_eq!("{foo:>w$.*}", spec.placeholder_for(&foo, Some(&width), Some(&next)));
+    /// let default = Specifier::default();
+    /// assert_eq!("{0}", default.placeholder_for(&ArgumentRef::Positional(0), None, None));
+    /// ```
+    pub fn placeholder_for(
+        &self,
+        arg: &ArgumentRef,
+        width: Option<&ArgumentRef>,
+        precision: Option<&ArgumentRef>,
+    ) -> String {
+        let mut placeholder = String::new();
+        // Writing to a `String` doesn't fail.
+        let _ = self.write_placeholder(&mut placeholder, arg, width, precision);
+        placeholder
+    }
+
+    /// Writes a substitution like `placeholder_for`, with anything that's written before the `:`,
+    /// such as an argument with its fallback, in place of the argument.
+    pub(crate) fn write_placeholder<W, A>(
+        &self,
+        out: &mut W,
+        arg: &A,
+        width: Option<&ArgumentRef>,
+        precision: Option<&ArgumentRef>,
+    ) -> fmt::Result
+    where
+        W: Write,
+        A: fmt::Display + ?Sized,
+    {
+        let mut spec = String::new();
+        self.write_with_refs(&mut spec, width, precision)?;
+        if spec.is_empty() {
+            write!(out, "{{{}}}", arg)
+        } else {
+            write!(out, "{{{}:{}}}", arg, spec)
+        }
+    }
+
+    /// Writes the specifier like its `Display` impl does, but with the width and precision taken
+    /// from the given arguments, if any.
+    fn write_with_refs<W: Write>(
+        &self,
+        f: &mut W,
+        width: Option<&ArgumentRef>,
+        precision: Option<&ArgumentRef>,
+    ) -> fmt::Result {
+        if let Some(fill) = self.fill.filter(|_| self.align != Align::None) {
+            write!(f, "{}", fill)?;
+        }
+        write!(f, "{}{}{}{}", self.align, self.sign, self.repr, self.pad)?;
+        match width {
+            Some(arg) => write_size_ref(f, arg)?,
+            None => write!(f, "{}", self.width)?,
+        }
+        write!(f, "{}{}", self.grouping, self.max_width)?;
+        match precision {
+            Some(arg) => {
+                f.write_char('.')?;
+                write_size_ref(f, arg)?;
+            }
+            None => write!(f, "{}", self.precision)?,
+        }
+        write!(f, "{}", self.format)
+    }
+
+    /// Parses a partial specifier, such as `>8` or `x`, and applies it over this one. Only the
+    /// parts that are written in `override_str` are changed.
+    ///
+    /// # Examples
+    /// ```
+    /// use rt_format::Specifier;
+    ///
+    /// let base: Specifier = "*^#10.2e".parse().unwrap();
+    /// assert_eq!("*>#12.2x", base.merge(">12x").unwrap().to_string());
+    /// ```
+    pub fn merge(&self, override_str: &str) -> Result<Specifier, ParseError> {
+        let partial: PartialSpecifier = override_str.parse()?;
+        Ok(partial.apply_to(self))
+    }
+}
+
+/// Writes the reference to the argument that supplies a width or precision, as in `1$`, `foo$` or
+/// `*`.
+fn write_size_ref<W: Write>(f: &mut W, arg: &ArgumentRef) -> fmt::Result {
+    match arg {
+        ArgumentRef::Next => f.write_char('*'),
+        _ => write!(f, "{}$", arg),
+    }
+}
+
+/// A format specifier whose parts are all optional, to be layered over a complete `Specifier`.
+///
+/// Parsing a `PartialSpecifier` sets only the parts that are written, so an explicit default, such
+/// as `.0`, can be told apart from a part that's missing. Like a `Specifier`, a partial specifier
+/// can't take its width or precision from an argument. The fill is only written together with the
+/// alignment, but it's optional on its own, so that `<` changes the alignment and keeps the fill.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Default)]
+pub struct PartialSpecifier {
+    /// Overrides `Specifier::fill`.
+    pub fill: Option<char>,
+    /// Overrides `Specifier::align`.
+    pub align: Option<Align>,
+    /// Overrides `Specifier::sign`.
+    pub sign: Option<Sign>,
+    /// Overrides `Specifier::repr`.
+    pub repr: Option<Repr>,
+    /// Overrides `Specifier::pad`.
+    pub pad: Option<Pad>,
+    /// Overrides `Specifier::width`.
+    pub width: Option<Width>,
+    /// Overrides `Specifier::grouping`.
+    pub grouping: Option<Grouping>,
+    /// Overrides `Specifier::max_width`.
+    pub max_width: Option<MaxWidth>,
+    /// Overrides `Specifier::precision`.
+    pub precision: Option<Precision>,
+    /// Overrides `Specifier::format`.
+    pub format: Option<Format>,
+}
+
+impl PartialSpecifier {
+    /// Returns a copy of `base` with the parts that are set in `self` replaced.
+    pub fn apply_to(&self, base: &Specifier) -> Specifier {
+        Specifier {
+            fill: self.fill.or(base.fill),
+            align: self.align.unwrap_or(base.align),
+            sign: self.sign.unwrap_or(base.sign),
+            repr: self.repr.unwrap_or(base.repr),
+            pad: self.pad.unwrap_or(base.pad),
+            width: self.width.unwrap_or(base.width),
+            grouping: self.grouping.unwrap_or(base.grouping),
+            max_width: self.max_width.unwrap_or(base.max_width),
+            locale: base.locale,
+            measure: base.measure,
+            precision: self.precision.unwrap_or(base.precision),
+            format: self.format.unwrap_or(base.format),
+        }
+    }
+}
+
+/// Builds a `Specifier` one dimension at a time, for when the format is computed rather than
+/// parsed from a string.
+///
+/// # Examples
+/// ```
+/// use rt_format::{Align, Format, Specifier};
+///
+/// let specifier = Specifier::builder()
+///     .align(Align::Right)
+///     .width(8)
+///     .format(Format::LowerHex)
+///     .build();
+/// assert_eq!(">8x", specifier.to_string());
+/// ```
+#[derive(Debug, Copy, Clone, Default)]
+pub struct SpecifierBuilder {
+    specifier: Specifier,
+}
+
+impl SpecifierBuilder {
+    /// Sets the fill character.
+    pub fn fill(mut self, fill: char) -> Self {
+        self.specifier.fill = Some(fill);
+        self
+    }
+
+    /// Sets the alignment.
+    pub fn align(mut self, align: Align) -> Self {
+        self.specifier.align = align;
+        self
+    }
+
+    /// Sets whether the sign is always emitted.
+    pub fn sign(mut self, sign: Sign) -> Self {
+        self.specifier.sign = sign;
+        self
+    }
+
+    /// Sets whether to use the alternate representation.
+    pub fn repr(mut self, repr: Repr) -> Self {
+        self.specifier.repr = repr;
+        self
+    }
+
+    /// Sets whether to pad with spaces or zeroes.
+    pub fn pad(mut self, pad: Pad) -> Self {
+        self.specifier.pad = pad;
+        self
+    }
+
+    /// Sets the minimum width.
+    pub fn width(mut self, width: usize) -> Self {
+        self.specifier.width = Width::AtLeast { width };
+        self
+    }
+
+    /// Sets the digit grouping.
+    pub fn grouping(mut self, grouping: Grouping) -> Self {
+        self.specifier.grouping = grouping;
+        self
+    }
+
+    /// Sets the maximum width.
+    pub fn max_width(mut self, max_width: MaxWidth) -> Self {
+        self.specifier.max_width = max_width;
+        self
+    }
+
+    /// Sets the locale.
+    pub fn locale(mut self, locale: Locale) -> Self {
+        self.specifier.locale = locale;
+        self
+    }
+
+    /// Sets how the width and the maximum width are measured.
+    pub fn measure(mut self, measure: Measure) -> Self {
+        self.specifier.measure = measure;
+        self
+    }
+
+    /// Sets the precision.
+    pub fn precision(mut self, precision: usize) -> Self {
+        self.specifier.precision = Precision::Exactly { precision };
+        self
+    }
+
+    /// Sets the format.
+    pub fn format(mut self, format: Format) -> Self {
+        self.specifier.format = format;
+        self
+    }
+
+    /// Returns the specification built so far. Every combination is representable, although the
+    /// arguments might not support all of them.
+    pub fn build(self) -> Specifier {
+        self.specifier
+    }
+}
+
+/// The `std::fmt` traits that `format_value` needs, one for each of the standard formats except the
+/// pointer format. It's implemented for every type that implements them.
+pub trait StdFormat:
+    fmt::Display
+    + fmt::Debug
+    + fmt::Octal
+    + fmt::LowerHex
+    + fmt::UpperHex
+    + fmt::Binary
+    + fmt::LowerExp
+    + fmt::UpperExp
+{
+}
+
+impl<T> StdFormat for T where
+    T: fmt::Display
+        + fmt::Debug
+        + fmt::Octal
+        + fmt::LowerHex
+        + fmt::UpperHex
+        + fmt::Binary
+        + fmt::LowerExp
+        + fmt::UpperExp
+        + ?Sized
+{
+}
+
+/// Formats the given value using the given formatter and the given format specification.
+/// 
+/// Since the implementation of `format_value` employs the `write!` macro, the `value` must
+/// implement the `std::fmt` formatting traits, see `StdFormat`. Which trait will actually be used
+/// is determined at runtime, based on the contents of the `specifier`. Few types implement
+/// `std::fmt::Pointer`, so it's left out, and the pointer format returns an error here. The
+/// arguments of a formatting string are formatted with it through `FormatArgument::fmt_pointer`.
+pub fn format_value<V>(specifier: &Specifier, value: &V, f: &mut fmt::Formatter) -> fmt::Result
+where
+    V: StdFormat,
+{
+    if specifier.max_width != MaxWidth::Unlimited {
+        return format_truncated(specifier, value, f);
+    }
+    if specifier.locale != Locale::C && specifier.format == Format::Display {
+        return format_localized(specifier, value, f);
+    }
+    if let Some(separator) = specifier.grouping.separator() {
+        if let Some(group_len) = group_len(specifier.format) {
+            return format_grouped(specifier, value, separator, group_len, f);
+        }
+    }
+    // With a measure other than `char`s, padding is done here even without a fill character,
+    // since `std::fmt` would measure the text in `char`s. The exception is the debug format, which
+    // passes the width on to the fields of a value instead of padding the whole output.
+    let fill = match specifier.fill {
+        None if specifier.measure != Measure::Chars && specifier.format != Format::Debug => {
+            Some(' ')
+        }
+        fill => fill,
+    };
+    match (fill, specifier.width) {
+        (Some(fill), Width::AtLeast { width }) if specifier.align != Align::None => {
+            let unpadded = Specifier {
+                fill: None,
+                align: Align::None,
+                pad: Pad::Space,
+                width: Width::Auto,
+                ..*specifier
+            };
+            let text = Unpadded(&unpadded, value).to_string();
+            if specifier.pad == Pad::Zero && pads_with_zeroes(&unpadded, value, &text) {
+                // Like in `format!`, zero padding overrides the fill and the alignment.
+                let zero_padded = Specifier {
+                    fill: None,
+                    ..*specifier
+                };
+                return format_std(&zero_padded, value, f);
+            }
+            pad_with_fill(&text, fill, specifier.align, width, specifier.measure, f)
+        }
+        _ => format_std(specifier, value, f),
+    }
+}
+
+/// Returns true if `std::fmt` pads the value with zeroes when asked to, which is only the case for
+/// numbers. The value is formatted one character wider than its `unpadded` text with zero padding,
+/// which gives the text followed by a space if the zero padding is ignored.
+fn pads_with_zeroes<V: StdFormat>(unpadded: &Specifier, value: &V, text: &str) -> bool {
+    let probe = Specifier {
+        pad: Pad::Zero,
+        width: Width::AtLeast {
+            width: text.chars().count() + 1,
+        },
+        ..*unpadded
+    };
+    let probed = Unpadded(&probe, value).to_string();
+    !(probed.ends_with(' ') && probed[..probed.len() - 1] == *text)
+}
+
+/// Formats the given value like `format_value`, but only with the display format, so that the
+/// `value` only has to implement `std::fmt::Display`. Returns an error if the `specifier` requests
+/// any other format.
+pub fn format_display_only<V>(
+    specifier: &Specifier,
+    value: &V,
+    f: &mut fmt::Formatter,
+) -> fmt::Result
+where
+    V: fmt::Display + ?Sized,
+{
+    if specifier.format != Format::Display {
+        return Err(fmt::Error);
+    }
+    format_value(specifier, &DisplayOnly(value), f)
+}
+
+/// Implements all the `std::fmt` formatting traits with the `Display` of the wrapped value, so that
+/// it can be passed to `format_value` with the display format.
+struct DisplayOnly<'a, V: ?Sized>(&'a V);
+
+macro_rules! impl_display_only {
+    ($($fmt_trait:ident),+) => {
+        $(
+            impl<'a, V: fmt::Display + ?Sized> fmt::$fmt_trait for DisplayOnly<'a, V> {
+                fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                    fmt::Display::fmt(self.0, f)
+                }
+            }
+        )+
+    };
+}
+
+impl_display_only!(Display, Debug, Octal, LowerHex, UpperHex, Binary, LowerExp, UpperExp);
+
+/// Returns the number of digits in a group for the given format, or `None` if the format doesn't
+/// support grouping.
+fn group_len(format: Format) -> Option<usize> {
+    match format {
+        Format::Display => Some(3),
+        Format::Octal | Format::LowerHex | Format::UpperHex | Format::Binary => Some(4),
+        _ => None,
+    }
+}
+
+/// Formats the value without padding, inserts the separators between groups of digits, and then
+/// pads the result the way `std::fmt` pads numbers.
+fn format_grouped<V>(
+    specifier: &Specifier,
+    value: &V,
+    separator: char,
+    group_len: usize,
+    f: &mut fmt::Formatter,
+) -> fmt::Result
+where
+    V: StdFormat,
+{
+    let unpadded = Specifier {
+        fill: None,
+        align: Align::None,
+        pad: Pad::Space,
+        width: Width::Auto,
+        grouping: Grouping::None,
+        ..*specifier
+    };
+    let text = Unpadded(&unpadded, value).to_string();
+
+    let mut prefix_len = if text.starts_with(['+', '-']) { 1 } else { 0 };
+    if specifier.repr == Repr::Alt && specifier.format != Format::Display {
+        prefix_len = text.len().min(prefix_len + 2);
+    }
+    let (prefix, rest) = text.split_at(prefix_len);
+    let digits_len = match specifier.format {
+        Format::LowerHex | Format::UpperHex => rest.find(|c: char| !c.is_ascii_hexdigit()),
+        _ => rest.find(|c: char| !c.is_ascii_digit()),
+    }
+    .unwrap_or(rest.len());
+    let (digits, suffix) = rest.split_at(digits_len);
+
+    let mut grouped = String::with_capacity(text.len() + digits_len / group_len);
+    grouped.push_str(prefix);
+    for (idx, digit) in digits.chars().enumerate() {
+        if idx > 0 && (digits_len - idx) % group_len == 0 {
+            grouped.push(separator);
+        }
+        grouped.push(digit);
+    }
+    grouped.push_str(suffix);
+    pad_number(specifier, &grouped, prefix_len, f)
+}
+
+/// Formats the value without padding, and if the output looks like a decimal number, rewrites it
+/// with the separators of the locale and pads the result the way `std::fmt` pads numbers.
+/// Otherwise, formats the value as if there was no locale.
+fn format_localized<V>(specifier: &Specifier, value: &V, f: &mut fmt::Formatter) -> fmt::Result
+where
+    V: StdFormat,
+{
+    let unlocalized = Specifier {
+        locale: Locale::C,
+        ..*specifier
+    };
+    let unpadded = Specifier {
+        fill: None,
+        align: Align::None,
+        pad: Pad::Space,
+        width: Width::Auto,
+        grouping: Grouping::None,
+        ..unlocalized
+    };
+    let text = Unpadded(&unpadded, value).to_string();
+
+    let sign_len = if text.starts_with(['+', '-']) { 1 } else { 0 };
+    let (sign, number) = text.split_at(sign_len);
+    let (integer, fraction) = match number.split_once('.') {
+        Some((integer, fraction)) => (integer, Some(fraction)),
+        None => (number, None),
+    };
+    let is_digits = |part: &str| !part.is_empty() && part.bytes().all(|b| b.is_ascii_digit());
+    if !is_digits(integer) || fraction.is_some_and(|fraction| !is_digits(fraction)) {
+        return format_value(&unlocalized, value, f);
+    }
+
+    let locale = specifier.locale;
+    let (separator, group_len) = match specifier.grouping.separator() {
+        Some(separator) => (Some(separator), 3),
+        None => (locale.grouping_separator, locale.grouping_size),
+    };
+    let mut localized = String::with_capacity(text.len() * 2);
+    localized.push_str(sign);
+    for (idx, digit) in integer.chars().enumerate() {
+        if let Some(separator) = separator {
+            if idx > 0 && group_len > 0 && (integer.len() - idx) % group_len == 0 {
+                localized.push(separator);
+            }
+        }
+        localized.push(digit);
+    }
+    if let Some(fraction) = fraction {
+        localized.push(locale.decimal_separator);
+        localized.push_str(fraction);
+    }
+    pad_number(specifier, &localized, sign_len, f)
+}
+
+/// Writes the text of a formatted number, padded to the width of the specifier the way `std::fmt`
+/// pads numbers. With zero padding, the zeroes go after the first `prefix_len` bytes of the text.
+fn pad_number(
+    specifier: &Specifier,
+    text: &str,
+    prefix_len: usize,
+    f: &mut fmt::Formatter,
+) -> fmt::Result {
+    match specifier.width {
+        Width::Auto => f.write_str(text),
+        Width::AtLeast { width } if specifier.pad == Pad::Zero => {
+            let padding = width.saturating_sub(text.chars().count());
+            f.write_str(&text[..prefix_len])?;
+            for _ in 0..padding {
+                f.write_char('0')?;
+            }
+            f.write_str(&text[prefix_len..])
+        }
+        Width::AtLeast { width } => {
+            let align = match specifier.align {
+                Align::None => Align::Right,
+                align => align,
+            };
+            pad_with_fill(text, specifier.fill.unwrap_or(' '), align, width, specifier.measure, f)
+        }
+    }
+}
+
+/// Formats the value without truncating it, and then truncates and pads the output. If the output
+/// fits, the value is formatted as usual, so that it gets the default alignment of its type.
+fn format_truncated<V>(specifier: &Specifier, value: &V, f: &mut fmt::Formatter) -> fmt::Result
+where
+    V: StdFormat,
+{
+    let untruncated = Specifier {
+        max_width: MaxWidth::Unlimited,
+        ..*specifier
+    };
+    let unpadded = Specifier {
+        fill: None,
+        align: Align::None,
+        pad: Pad::Space,
+        width: Width::Auto,
+        ..untruncated
+    };
+    let (max_width, ellipsis) = match specifier.max_width {
+        MaxWidth::Unlimited => return format_value(&untruncated, value, f),
+        MaxWidth::Cut { width } => (width, false),
+        MaxWidth::Ellipsis { width } => (width, width > 0),
+    };
+    let text = Unpadded(&unpadded, value).to_string();
+    let len = specifier.measure.text_width(&text);
+    if len <= max_width {
+        return match specifier.width {
+            Width::AtLeast { width } if width > len => format_value(&untruncated, value, f),
+            _ => f.write_str(&text),
+        };
+    }
+
+    let mut truncated = String::with_capacity(text.len());
+    let mut truncated_len = 0;
+    for c in text.chars() {
+        truncated_len += specifier.measure.char_width(c);
+        if truncated_len > max_width - ellipsis as usize {
+            break;
+        }
+        truncated.push(c);
+    }
+    if ellipsis {
+        truncated.push('…');
+    }
+    match specifier.width {
+        Width::AtLeast { width } => {
+            let fill = specifier.fill.unwrap_or(' ');
+            pad_with_fill(&truncated, fill, specifier.align, width, specifier.measure, f)
+        }
+        Width::Auto => f.write_str(&truncated),
+    }
+}
+
+/// Formats a value with `format_value`, so that it can be measured before padding. The specifier
+/// is expected to have no width.
+struct Unpadded<'a, V>(&'a Specifier, &'a V);
+
+impl<'a, V> fmt::Display for Unpadded<'a, V>
+where
+    V: StdFormat,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        format_value(self.0, self.1, f)
+    }
+}
+
+/// Writes the text, padded to the given width with the fill character. The text is measured with
+/// the given measure, and like in `std::fmt`, the extra fill character goes after the text when
+/// centering.
+fn pad_with_fill(
+    text: &str,
+    fill: char,
+    align: Align,
+    width: usize,
+    measure: Measure,
+    f: &mut fmt::Formatter,
+) -> fmt::Result {
+    let padding = width.saturating_sub(measure.text_width(text));
+    let (before, after) = match align {
+        Align::Right => (padding, 0),
+        Align::Center => (padding / 2, padding - padding / 2),
+        Align::Left | Align::None => (0, padding),
+    };
+    for _ in 0..before {
+        f.write_char(fill)?;
+    }
+    f.write_str(text)?;
+    for _ in 0..after {
+        f.write_char(fill)?;
+    }
+    Ok(())
+}
+
+/// Parses the formatting string, using given positional and named arguments, and formats it into
+/// a `String`. This is the runtime equivalent of the `format!` macro.
+pub fn try_format<'a, V, P, N>(
+    format: &'a str,
+    positional: &'a P,
+    named: &'a N,
+) -> Result<String, ParseError>
+where
+    V: 'a + FormatArgument,
+    P: PositionalArguments<'a, V> + ?Sized,
+    N: NamedArguments<V> + ?Sized,
+{
+    ParsedFormat::parse(format, positional, named).map(|parsed| parsed.to_string())
+}
+
+/// Like `try_format`, but with positional arguments only.
+pub fn try_format_positional<'a, V, P>(
+    format: &'a str,
+    positional: &'a P,
+) -> Result<String, ParseError>
+where
+    V: 'a + FormatArgument,
+    P: PositionalArguments<'a, V> + ?Sized,
+{
+    try_format(format, positional, &NoNamedArguments)
+}
+
+/// Like `try_format`, but with named arguments only.
+pub fn try_format_named<'a, V, N>(format: &'a str, named: &'a N) -> Result<String, ParseError>
+where
+    V: 'a + FormatArgument,
+    N: NamedArguments<V> + ?Sized,
+{
+    try_format(format, &NoPositionalArguments, named)
+}
+
+/// Parses the formatting string and writes each segment to `out` as soon as it is parsed, without
+/// collecting the segments first. Formatting stops at the first error, so the text before the
+/// offending segment has already been written when a parse error is returned.
+pub fn format_streaming<'a, V, P, N, W>(
+    format: &'a str,
+    positional: &'a P,
+    named: &'a N,
+    out: &mut W,
+) -> Result<(), FormatError>
+where
+    V: 'a + FormatArgument,
+    P: PositionalArguments<'a, V> + ?Sized,
+    N: NamedArguments<V> + ?Sized,
+    W: fmt::Write + ?Sized,
+{
+    for segment in Parser::new(format, positional, named) {
+        write!(out, "{}", segment?)?;
+    }
+    Ok(())
+}
+
+/// Logs a message with a runtime formatting string, like `log::log!` does with a literal one. Takes
+/// the level, the formatting string, the positional arguments and the named arguments, and
+/// evaluates to `Result<(), ParseError>`.
+///
+/// The formatting string is only parsed if the level is enabled. The parsed segments are passed to
+/// the logger through `format_args!`, so the message is formatted by the logger itself, without an
+/// intermediate `String`. The only allocation is the `Vec` of segments made by
+/// `ParsedFormat::parse`. If parsing fails, nothing is logged and the error is returned.
+///
+/// # Examples
+/// ```
+/// use rt_format::argument::NoNamedArguments;
+/// use rt_format::{rt_log, StdValue};
+///
+/// let template = String::from("{} of {} done");
+/// rt_log!(log::Level::Info, &template, &[StdValue(3), StdValue(5)], &NoNamedArguments).unwrap();
+/// ```
+#[cfg(feature = "log")]
+#[macro_export]
+macro_rules! rt_log {
+    ($level:expr, $format:expr, $positional:expr, $named:expr $(,)?) => {{
+        let level: $crate::__log::Level = $level;
+        if $crate::__log::log_enabled!(level) {
+            $crate::ParsedFormat::parse($format, $positional, $named)
+                .map(|parsed| $crate::__log::log!(level, "{}", parsed))
+        } else {
+            ::core::result::Result::<(), $crate::ParseError>::Ok(())
+        }
+    }};
+}
@@ -1,355 +1,2624 @@
 //! Provides support for parsing typical Rust formatting strings.
 //! 
 //! The parser supports all of the features of the formatting strings that are normally passed to
-//! the `format!` macro, except for the fill character.
+//! the `format!` macro.
 
-use regex::{Captures, Match};
-use std::convert::{TryFrom, TryInto};
-use std::fmt;
+use alloc::borrow::Cow;
+use alloc::collections::BTreeMap;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::convert::{TryFrom, TryInto};
+use core::fmt;
+use core::ops::Range;
+use core::str::FromStr;
+use unicode_xid::UnicodeXID;
+#[cfg(feature = "std")]
+use std::error;
+#[cfg(feature = "std")]
+use std::io;
 
 use crate::argument::{
-    ArgumentFormatter, ArgumentSource, FormatArgument, NamedArguments, PositionalArguments
+    format_argument, ArgumentSource, FormatArgument, NamedArguments, NoNamedArguments,
+    NoPositionalArguments, PositionalArguments
 };
-use crate::{format_value, Align, Format, Pad, Precision, Repr, Sign, Specifier, Width};
+use crate::{
+    Align, Format, Grouping, Locale, MaxWidth, Measure, Pad, PartialSpecifier, Precision, Repr,
+    Sign, Specifier, Width
+};
+
+/// The reason why the formatting string could not be parsed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseErrorKind {
+    /// A brace that is neither escaped nor part of a well-formed substitution.
+    UnmatchedBrace,
+    /// The argument reference or the format specifier is malformed.
+    InvalidSpecifier,
+    /// The substitution refers to a named argument that doesn't exist.
+    MissingNamedArgument {
+        /// The name of the argument, as written in the formatting string.
+        name: String,
+    },
+    /// The substitution refers to a positional argument that doesn't exist. For an implicit
+    /// reference, such as `{}` or `{:.*}`, the index is the first one past the available arguments.
+    ArgumentIndexOutOfRange {
+        /// The index of the requested argument.
+        index: usize,
+        /// The number of positional arguments that were provided.
+        available: usize,
+    },
+    /// The substitution refers to a positional argument by its distance from the end, e.g. `{-3}`,
+    /// but there are fewer positional arguments than that.
+    NegativeIndexOutOfRange {
+        /// The distance of the requested argument from the end, e.g. 3 for `{-3}`.
+        distance: usize,
+        /// The number of positional arguments that were provided.
+        available: usize,
+    },
+    /// The argument doesn't support the requested format.
+    UnsupportedFormat {
+        /// The reference to the argument, as written in the formatting string, or `None` for a
+        /// value that wasn't taken from an argument, see `Substitution::new`.
+        argument: Option<ArgumentRef>,
+        /// The requested format, with the width and precision already looked up.
+        specifier: Specifier,
+    },
+    /// The width or precision refers to an argument, but the specifier was parsed on its own, so
+    /// there are no arguments. Only reported by `parse_specifier_str`.
+    RequiresArgument {
+        /// The reference to the argument, as written in the specifier.
+        argument: ArgumentRef,
+    },
+    /// The argument used as width or precision cannot be converted to `usize`.
+    InvalidSizeArgument,
+    /// The width or precision that's written in the formatting string doesn't fit in `usize`,
+    /// regardless of the `FormatLimits`.
+    WidthOverflow,
+    /// The width or precision is larger than allowed by the `FormatLimits` in effect.
+    LimitExceeded {
+        /// The requested width or precision.
+        size: usize,
+        /// The largest width or precision that is allowed.
+        limit: usize,
+    },
+    /// Some of the arguments aren't used by the formatting string. Only reported by a parser that
+    /// checks for them, see `Parser::with_strict_arguments`.
+    UnusedArguments {
+        /// The indices of the unused positional arguments, in ascending order.
+        positional: Vec<usize>,
+        /// The names of the unused named arguments, in ascending order.
+        named: Vec<String>,
+    },
+}
+
+impl fmt::Display for ParseErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParseErrorKind::UnmatchedBrace => f.write_str("unmatched brace"),
+            ParseErrorKind::InvalidSpecifier => f.write_str("invalid format specifier"),
+            ParseErrorKind::MissingNamedArgument { name } => {
+                write!(f, "missing named argument `{}`", name)
+            }
+            ParseErrorKind::ArgumentIndexOutOfRange { index, available } => write!(
+                f,
+                "argument {} requested, but only {} provided",
+                index, available
+            ),
+            ParseErrorKind::NegativeIndexOutOfRange { distance, available } => write!(
+                f,
+                "argument -{} requested, but only {} provided",
+                distance, available
+            ),
+            ParseErrorKind::UnsupportedFormat {
+                argument,
+                specifier,
+            } => {
+                match argument {
+                    Some(ArgumentRef::Positional(index)) => write!(f, "argument {} ", index)?,
+                    Some(ArgumentRef::FromEnd(distance)) => write!(f, "argument -{} ", distance)?,
+                    Some(ArgumentRef::Next) | None => f.write_str("argument ")?,
+                    Some(ArgumentRef::Named(name)) => write!(f, "argument `{}` ", name)?,
+                }
+                write!(f, "does not support the format `:{}`", specifier)
+            }
+            ParseErrorKind::RequiresArgument { argument } => match argument {
+                ArgumentRef::Positional(index) => {
+                    write!(f, "width or precision requires argument {}", index)
+                }
+                ArgumentRef::FromEnd(distance) => {
+                    write!(f, "width or precision requires argument -{}", distance)
+                }
+                ArgumentRef::Next => f.write_str("width or precision requires the next argument"),
+                ArgumentRef::Named(name) => {
+                    write!(f, "width or precision requires argument `{}`", name)
+                }
+            },
+            ParseErrorKind::InvalidSizeArgument => {
+                f.write_str("argument cannot be used as width or precision")
+            }
+            ParseErrorKind::WidthOverflow => f.write_str("width or precision is too large"),
+            ParseErrorKind::LimitExceeded { size, limit } => write!(
+                f,
+                "width or precision {} exceeds the limit of {}",
+                size, limit
+            ),
+            ParseErrorKind::UnusedArguments { positional, named } => {
+                f.write_str("unused arguments: ")?;
+                for (idx, index) in positional.iter().enumerate() {
+                    let separator = if idx > 0 { ", " } else { "" };
+                    write!(f, "{}{}", separator, index)?;
+                }
+                for (idx, name) in named.iter().enumerate() {
+                    let separator = if idx > 0 || !positional.is_empty() { ", " } else { "" };
+                    write!(f, "{}`{}`", separator, name)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// An error that occurred while parsing the formatting string.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    span: Range<usize>,
+    kind: ParseErrorKind,
+}
+
+impl ParseError {
+    /// Creates a new `ParseError` of the given kind, at the given byte offset. The span of the
+    /// error is empty.
+    pub fn new(offset: usize, kind: ParseErrorKind) -> Self {
+        Self::with_span(offset..offset, kind)
+    }
+
+    /// Creates a new `ParseError` of the given kind, covering the given range of bytes.
+    pub fn with_span(span: Range<usize>, kind: ParseErrorKind) -> Self {
+        ParseError { span, kind }
+    }
+
+    /// The byte offset in the formatting string where the parsing failed.
+    pub fn offset(&self) -> usize {
+        self.span.start
+    }
+
+    /// The range of bytes in the formatting string that caused the error. For an error in a
+    /// substitution, this is the whole substitution, including the braces, and for an unmatched
+    /// brace, it's just the brace. It's empty if the error can't be pinned to a part of the
+    /// formatting string, such as an unused argument.
+    pub fn span(&self) -> Range<usize> {
+        self.span.clone()
+    }
+
+    /// The reason why the parsing failed.
+    pub fn kind(&self) -> &ParseErrorKind {
+        &self.kind
+    }
+
+    /// Converts the offset of the error to a line and column in `source`, which should be the
+    /// formatting string that was parsed. The line is 1-based, and the column is the 0-based number
+    /// of `char`s between the start of the line and the offset. Each `\n`, `\r\n` and lone `\r`
+    /// ends a line.
+    ///
+    /// # Examples
+    /// ```
+    /// use rt_format::{NoNamedArguments, ParsedFormat, StdValue};
+    ///
+    /// let args = [StdValue(1)];
+    /// let format = "first: {}\r\nsecond: {1}";
+    /// let err = ParsedFormat::parse(format, &args, &NoNamedArguments).unwrap_err();
+    /// assert_eq!((2, 8), err.line_col(format));
+    /// ```
+    pub fn line_col(&self, source: &str) -> (usize, usize) {
+        let mut line = 1;
+        let mut column = 0;
+        let mut after_cr = false;
+        for (idx, c) in source.char_indices() {
+            if idx >= self.span.start {
+                break;
+            }
+            match c {
+                '\n' if after_cr => (),
+                '\r' | '\n' => {
+                    line += 1;
+                    column = 0;
+                }
+                _ => column += 1,
+            }
+            after_cr = c == '\r';
+        }
+        (line, column)
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} at offset {}", self.kind, self.span.start)
+    }
+}
+
+#[cfg(feature = "std")]
+impl error::Error for ParseError {}
+
+/// The reason why a formatting string could not be formatted while it was being parsed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FormatError {
+    /// The formatting string could not be parsed or bound to the arguments.
+    Parse(ParseError),
+    /// The output failed to accept the formatted text.
+    Write(fmt::Error),
+}
+
+impl From<ParseError> for FormatError {
+    fn from(error: ParseError) -> Self {
+        FormatError::Parse(error)
+    }
+}
+
+impl From<fmt::Error> for FormatError {
+    fn from(error: fmt::Error) -> Self {
+        FormatError::Write(error)
+    }
+}
+
+impl fmt::Display for FormatError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            FormatError::Parse(error) => error.fmt(f),
+            FormatError::Write(error) => error.fmt(f),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl error::Error for FormatError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            FormatError::Parse(error) => Some(error),
+            FormatError::Write(error) => Some(error),
+        }
+    }
+}
+
+/// A reference to the argument that supplies a value, as written in the formatting string.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum ArgumentRef {
+    /// The positional argument with the given index, e.g. `{0}` or `{:1$}`.
+    Positional(usize),
+    /// The positional argument at the given distance from the end, e.g. `{-1}` for the last one.
+    /// Only accepted by a parser that allows negative indices, see `Parser::with_negative_indices`.
+    FromEnd(usize),
+    /// The next positional argument, e.g. `{}` or `{:.*}`.
+    Next,
+    /// The named argument with the given name, e.g. `{foo}` or `{:foo$}`.
+    Named(String),
+}
+
+/// Writes the argument as it's written in a substitution, without the braces, e.g. `0`, `-1` or
+/// `foo`. The next positional argument is written as nothing at all, as in `{}`.
+impl fmt::Display for ArgumentRef {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ArgumentRef::Positional(index) => write!(f, "{}", index),
+            ArgumentRef::FromEnd(distance) => write!(f, "-{}", distance),
+            ArgumentRef::Next => Ok(()),
+            ArgumentRef::Named(name) => f.write_str(name),
+        }
+    }
+}
+
+/// What to do with a substitution whose argument, or the argument for its width or precision, is
+/// missing.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Default)]
+pub enum MissingBehavior {
+    /// Fail with `ParseErrorKind::MissingNamedArgument` or
+    /// `ParseErrorKind::ArgumentIndexOutOfRange`.
+    #[default]
+    Error,
+    /// Keep the substitution as it's written in the formatting string, braces included, so that
+    /// the output can be used as a formatting string again, with other arguments. For the same
+    /// reason, the escaped braces in the literal text are kept escaped, as `{{` and `}}`.
+    Passthrough,
+}
 
 /// A value and its formatting specifier.
-#[derive(Debug, Copy, Clone, PartialEq)]
+///
+/// It implements `Display`, formatting the value as the specifier requests, so it can be written
+/// on its own with `write!(w, "{}", substitution)`.
+#[derive(Debug, Clone, PartialEq)]
 pub struct Substitution<'v, V: FormatArgument> {
     specifier: Specifier,
     value: &'v V,
-    _private: (),
+    argument: Option<ArgumentRef>,
+    span: Range<usize>,
+    shortcut: Shortcut,
+    unsupported: Option<Unsupported<'v, V>>,
+}
+
+/// A function that formats a value that doesn't support the format its specifier requests, in
+/// place of the formatting functions of the value. See `Parser::with_unsupported_formatter`.
+pub type UnsupportedFormatter<V> = dyn Fn(&Specifier, &V, &mut fmt::Formatter) -> fmt::Result;
+
+/// The `UnsupportedFormatter` of a `Substitution`. Two of them are equal if they're the same
+/// function.
+struct Unsupported<'v, V>(&'v UnsupportedFormatter<V>);
+
+impl<'v, V> Clone for Unsupported<'v, V> {
+    fn clone(&self) -> Self {
+        Unsupported(self.0)
+    }
+}
+
+impl<'v, V> fmt::Debug for Unsupported<'v, V> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("UnsupportedFormatter")
+    }
+}
+
+impl<'v, V> PartialEq for Unsupported<'v, V> {
+    fn eq(&self, other: &Self) -> bool {
+        core::ptr::addr_eq(self.0, other.0)
+    }
+}
+
+/// How a `Substitution` can skip `format_argument`, which is decided when it's created. For `{}`
+/// and `{:?}`, the formatting function of the value can be called directly, instead of going
+/// through the matching on the specifier and the `write!` in `format_value`. That takes about half
+/// the time for a formatting string of plain `{}` substitutions, but makes no measurable difference
+/// once other specifiers are mixed in; see the numbers in `benches/format.rs`.
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum Shortcut {
+    /// The specifier is the default one, so the value is written with `fmt_display_with`.
+    Display,
+    /// The specifier only selects the debug format, so the value is written with `fmt_debug`.
+    Debug,
+    /// The value is written with `format_argument`.
+    None,
+}
+
+impl Shortcut {
+    fn for_specifier(specifier: &Specifier) -> Self {
+        let debug = Specifier {
+            format: Format::Debug,
+            ..Specifier::default()
+        };
+        if *specifier == Specifier::default() {
+            Shortcut::Display
+        } else if *specifier == debug {
+            Shortcut::Debug
+        } else {
+            Shortcut::None
+        }
+    }
+}
+
+/// Returns whether the formatter has none of the options that a formatting string can set, as is
+/// the case for the one that `write!` passes to the `Display` impl of a `{}` substitution.
+fn has_default_options(f: &fmt::Formatter) -> bool {
+    f.width().is_none()
+        && f.precision().is_none()
+        && f.align().is_none()
+        && !f.alternate()
+        && !f.sign_plus()
+        && !f.sign_minus()
+        && !f.sign_aware_zero_pad()
 }
 
 impl<'v, V: FormatArgument> Substitution<'v, V> {
-    /// Create an `Substitution` if the given value supports the given format.
-    pub fn new(specifier: Specifier, value: &'v V) -> Result<Substitution<'v, V>, ()> {
-        if value.supports_format(&specifier) {
+    /// Create an `Substitution` if the given value supports the given format. The substitution
+    /// doesn't refer to an argument, and its span is empty. If the value doesn't support the
+    /// format, returns `ParseErrorKind::UnsupportedFormat` with the specifier.
+    pub fn new(specifier: Specifier, value: &'v V) -> Result<Substitution<'v, V>, ParseErrorKind> {
+        Self::with_argument_ref(specifier, value, None, 0..0, None)
+    }
+
+    /// Creates the substitution, which formats the value with the `unsupported` formatter, if any,
+    /// if the value doesn't support the format.
+    fn with_argument_ref(
+        specifier: Specifier,
+        value: &'v V,
+        argument: Option<ArgumentRef>,
+        span: Range<usize>,
+        unsupported: Option<&'v UnsupportedFormatter<V>>,
+    ) -> Result<Substitution<'v, V>, ParseErrorKind> {
+        let supported = value.supports_format(&specifier);
+        if supported || unsupported.is_some() {
             Ok(Substitution {
+                shortcut: Shortcut::for_specifier(&specifier),
                 specifier,
                 value,
-                _private: (),
+                argument,
+                span,
+                unsupported: unsupported.filter(|_| !supported).map(Unsupported),
+            })
+        } else {
+            Err(ParseErrorKind::UnsupportedFormat {
+                argument,
+                specifier,
+            })
+        }
+    }
+
+    /// A reference to the formatting specifier.
+    pub fn specifier(&self) -> &Specifier {
+        &self.specifier
+    }
+
+    /// A reference to the value to format.
+    pub fn value(&self) -> &'v V {
+        self.value
+    }
+
+    /// A reference to the argument that the value was taken from, or `None` if the substitution
+    /// was created with `Substitution::new`.
+    pub fn argument_ref(&self) -> Option<&ArgumentRef> {
+        self.argument.as_ref()
+    }
+
+    /// The range of bytes in the formatting string that the substitution was parsed from,
+    /// including the braces.
+    pub fn span(&self) -> Range<usize> {
+        self.span.clone()
+    }
+}
+
+impl<'v, V: FormatArgument> fmt::Display for Substitution<'v, V> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if let Some(Unsupported(formatter)) = self.unsupported {
+            return formatter(&self.specifier, self.value, f);
+        }
+        match self.shortcut {
+            Shortcut::Display if has_default_options(f) => {
+                let specifier = Specifier {
+                    align: self.value.natural_alignment(),
+                    ..self.specifier
+                };
+                self.value.fmt_display_with(&specifier, f)
+            }
+            Shortcut::Debug if has_default_options(f) => self.value.fmt_debug(f),
+            _ => format_argument(&self.specifier, self.value, f),
+        }
+    }
+}
+
+/// A single segment of a formatting string.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Segment<'s, V: FormatArgument> {
+    /// Text to be sent to the formatter.
+    Text {
+        /// The text, with any escaped braces already unescaped.
+        text: &'s str,
+        /// The range of bytes in the formatting string that the text was parsed from. For an
+        /// escaped brace, this covers both braces.
+        span: Range<usize>,
+    },
+    /// A value ready to be formatted.
+    Substitution(Substitution<'s, V>),
+}
+
+impl<'s, V: FormatArgument> Segment<'s, V> {
+    /// The range of bytes in the formatting string that the segment was parsed from.
+    pub fn span(&self) -> Range<usize> {
+        match self {
+            Segment::Text { span, .. } => span.clone(),
+            Segment::Substitution(substitution) => substitution.span(),
+        }
+    }
+}
+
+impl<'s, V: FormatArgument> fmt::Display for Segment<'s, V> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Segment::Text { text, .. } => f.write_str(text),
+            Segment::Substitution(arg) => arg.fmt(f),
+        }
+    }
+}
+
+/// A representation of the formatting string and associated values, ready to be formatted.
+///
+/// It's created by `ParsedFormat::parse`, or by `Template::bind` when the same formatting string is
+/// used with different arguments.
+///
+/// A `std::fmt::Arguments` can't be built at runtime, but `ParsedFormat` implements `Display`, so
+/// it can be passed to `write!` and `writeln!` instead, with a `"{}"` formatting string. This works
+/// for any writer, including an `io::Write`, whose `write_fmt` takes `std::fmt::Arguments`.
+///
+/// Unlike in `format!`, a substitution can give a fallback to write if its argument is missing,
+/// after a `?` that follows the argument, as in `{host?localhost}` or `{port?8080:>5}`. The
+/// fallback is written as is, without applying the specifier, and it can't contain `:` or braces.
+/// It's also used if an argument that supplies the width or precision is missing.
+///
+/// # Examples
+/// ```
+/// use rt_format::{NoPositionalArguments, ParsedFormat, StdValue};
+///
+/// let named = [("port", StdValue(80))];
+/// let parsed =
+///     ParsedFormat::parse("{host?localhost}:{port?8080:<4}.", &NoPositionalArguments, &named);
+/// assert_eq!("localhost:80  .", parsed.unwrap().to_string());
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParsedFormat<'a, V: FormatArgument> {
+    segments: Vec<Segment<'a, V>>,
+}
+
+impl<'a, V: FormatArgument> ParsedFormat<'a, V> {
+    /// Parses the formatting string, using given positional and named arguments. Does not perform
+    /// any formatting. It just parses the formatting string, validates that all the arguments are
+    /// present, and that each argument supports the requested format.
+    pub fn parse<P, N>(format: &'a str, positional: &'a P, named: &'a N) -> Result<Self, ParseError>
+    where
+        P: PositionalArguments<'a, V> + ?Sized,
+        N: NamedArguments<V> + ?Sized,
+    {
+        Self::from_parser(Parser::new(format, positional, named))
+    }
+
+    /// Like `parse`, but with positional arguments only.
+    ///
+    /// # Examples
+    /// ```
+    /// use rt_format::{ParsedFormat, StdValue};
+    ///
+    /// let args = [StdValue(42), StdValue(7)];
+    /// let parsed = ParsedFormat::parse_positional("{1} of {0:#x}", &args).unwrap();
+    /// assert_eq!("7 of 0x2a", parsed.to_string());
+    /// ```
+    pub fn parse_positional<P>(format: &'a str, positional: &'a P) -> Result<Self, ParseError>
+    where
+        P: PositionalArguments<'a, V> + ?Sized,
+    {
+        Self::parse(format, positional, &NoNamedArguments)
+    }
+
+    /// Like `parse`, but with named arguments only.
+    pub fn parse_named<N>(format: &'a str, named: &'a N) -> Result<Self, ParseError>
+    where
+        N: NamedArguments<V> + ?Sized,
+    {
+        Self::parse(format, &NoPositionalArguments, named)
+    }
+
+    /// Collects the segments of the given parser, which makes it possible to parse with any of the
+    /// options of `Parser`, or to bind a template with them, see `Template::parser`.
+    ///
+    /// # Examples
+    /// ```
+    /// use rt_format::parser::{MissingBehavior, Parser};
+    /// use rt_format::{NoPositionalArguments, ParsedFormat, StdValue};
+    ///
+    /// let named = [("done", StdValue(3))];
+    /// let parser = Parser::new("{ done } of { total:>4 }", &NoPositionalArguments, &named)
+    ///     .with_trimmed_names()
+    ///     .with_missing_behavior(MissingBehavior::Passthrough);
+    /// let parsed = ParsedFormat::from_parser(parser).unwrap();
+    /// assert_eq!("3 of { total:>4 }", parsed.to_string());
+    /// ```
+    pub fn from_parser<P, N>(parser: Parser<'a, V, P, N>) -> Result<Self, ParseError>
+    where
+        P: PositionalArguments<'a, V> + ?Sized,
+        N: NamedArguments<V> + ?Sized,
+    {
+        let segments: Result<Vec<Segment<'a, V>>, ParseError> = parser.collect();
+        Ok(ParsedFormat {
+            segments: segments?,
+        })
+    }
+
+    /// The segments of the formatting string, in order.
+    pub fn segments(&self) -> &[Segment<'a, V>] {
+        &self.segments
+    }
+
+    /// Creates an `OwnedFormat` that doesn't borrow the formatting string or the arguments. This
+    /// allocates a `String` for each piece of literal text and clones each substituted value, so
+    /// it's only worth doing if the result has to outlive the inputs.
+    pub fn to_owned_format(&self) -> OwnedFormat<V>
+    where
+        V: Clone,
+    {
+        let segments = self
+            .segments
+            .iter()
+            .map(|segment| match segment {
+                Segment::Text { text, .. } => OwnedSegment::Text(text.to_string()),
+                Segment::Substitution(sub) if sub.unsupported.is_some() => {
+                    OwnedSegment::Text(sub.to_string())
+                }
+                Segment::Substitution(sub) => OwnedSegment::Substitution {
+                    specifier: sub.specifier,
+                    value: sub.value.clone(),
+                },
+            })
+            .collect();
+        OwnedFormat { segments }
+    }
+
+    /// Writes the formatted segments directly into the given writer, without allocating an
+    /// intermediate `String`.
+    pub fn write_to<W: fmt::Write + ?Sized>(&self, w: &mut W) -> fmt::Result {
+        for segment in &self.segments {
+            match segment {
+                Segment::Text { text, .. } => w.write_str(text)?,
+                Segment::Substitution(substitution) => write!(w, "{}", substitution)?,
+            }
+        }
+        Ok(())
+    }
+
+    /// Estimates the length of the formatted output, in bytes. The estimate is a lower bound: the
+    /// length of the literal text plus the minimum width of each substitution, if it has one.
+    pub fn estimated_len(&self) -> usize {
+        self.segments
+            .iter()
+            .map(|segment| match segment {
+                Segment::Text { text, .. } => text.len(),
+                Segment::Substitution(substitution) => match substitution.specifier().width {
+                    Width::AtLeast { width } => width,
+                    Width::Auto => 0,
+                },
+            })
+            .sum()
+    }
+
+    /// Formats the segments into a new `String`, which reserves the `estimated_len` up front, so
+    /// that it doesn't have to grow as often as the one returned by `to_string`.
+    ///
+    /// # Panics
+    /// Like `to_string`, panics if an argument's formatting function returns an error.
+    pub fn format_into_string(&self) -> String {
+        let mut result = String::with_capacity(self.estimated_len());
+        self.write_to(&mut result)
+            .expect("a formatting function returned an error unexpectedly");
+        result
+    }
+
+    /// Formats the segments, borrowing the text of the formatting string instead of allocating if
+    /// there's nothing to substitute. Escaped braces split the text into several segments, so a
+    /// formatting string that contains them is still formatted into a new `String`.
+    ///
+    /// # Panics
+    /// Like `to_string`, panics if an argument's formatting function returns an error.
+    pub fn to_cow(&self) -> Cow<'a, str> {
+        match self.segments.as_slice() {
+            [] => Cow::Borrowed(""),
+            [Segment::Text { text, .. }] => Cow::Borrowed(text),
+            _ => Cow::Owned(self.format_into_string()),
+        }
+    }
+
+    /// Returns a value whose `Display` implementation formats the segments. This is the same as
+    /// using `self` directly, but it makes the intent clearer where a `Display` value is expected.
+    ///
+    /// # Examples
+    /// ```
+    /// use std::io::Write;
+    /// use rt_format::{NoNamedArguments, ParsedFormat, StdValue};
+    ///
+    /// let args = [StdValue(7)];
+    /// let parsed = ParsedFormat::parse("{:03}", &args, &NoNamedArguments).unwrap();
+    /// let mut out = Vec::new();
+    /// writeln!(out, "[{}]", parsed.as_display()).unwrap();
+    /// assert_eq!(b"[007]\n", out.as_slice());
+    /// ```
+    pub fn as_display(&self) -> impl fmt::Display + '_ {
+        self
+    }
+
+    /// Writes the formatted segments directly into the given `io::Write`. An I/O error from the
+    /// writer is returned as is, while a formatting error is returned as an `io::Error` of kind
+    /// `Other`.
+    #[cfg(feature = "std")]
+    pub fn write_io<W: io::Write + ?Sized>(&self, w: &mut W) -> io::Result<()> {
+        let mut adapter = IoAdapter {
+            inner: w,
+            error: None,
+        };
+        self.write_to(&mut adapter)
+            .map_err(|_| adapter.error.unwrap_or_else(|| io::Error::other("formatting error")))
+    }
+}
+
+/// Adapts an `io::Write` to `fmt::Write`, keeping the I/O error that caused a write to fail.
+#[cfg(feature = "std")]
+struct IoAdapter<'w, W: io::Write + ?Sized> {
+    inner: &'w mut W,
+    error: Option<io::Error>,
+}
+
+#[cfg(feature = "std")]
+impl<'w, W: io::Write + ?Sized> fmt::Write for IoAdapter<'w, W> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.inner.write_all(s.as_bytes()).map_err(|err| {
+            self.error = Some(err);
+            fmt::Error
+        })
+    }
+}
+
+impl<'a, V: FormatArgument> fmt::Display for ParsedFormat<'a, V> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.write_to(f)
+    }
+}
+
+impl<'a, V: FormatArgument> IntoIterator for ParsedFormat<'a, V> {
+    type Item = Segment<'a, V>;
+    type IntoIter = alloc::vec::IntoIter<Segment<'a, V>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.segments.into_iter()
+    }
+}
+
+impl<'p, 'a, V: FormatArgument> IntoIterator for &'p ParsedFormat<'a, V> {
+    type Item = &'p Segment<'a, V>;
+    type IntoIter = core::slice::Iter<'p, Segment<'a, V>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.segments.iter()
+    }
+}
+
+/// A single segment of an `OwnedFormat`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum OwnedSegment<V: FormatArgument> {
+    /// Text to be sent to the formatter.
+    Text(String),
+    /// A value ready to be formatted, with its formatting specifier.
+    Substitution {
+        /// The formatting specifier.
+        specifier: Specifier,
+        /// The value to format.
+        value: V,
+    },
+}
+
+impl<V: FormatArgument> fmt::Display for OwnedSegment<V> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            OwnedSegment::Text(text) => f.write_str(text),
+            OwnedSegment::Substitution { specifier, value } => format_argument(specifier, value, f),
+        }
+    }
+}
+
+/// A `ParsedFormat` that owns its text and values, so it can be stored without keeping the
+/// formatting string and the arguments around. Created with `ParsedFormat::to_owned_format`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OwnedFormat<V: FormatArgument> {
+    segments: Vec<OwnedSegment<V>>,
+}
+
+impl<V: FormatArgument> OwnedFormat<V> {
+    /// The segments of the formatting string, in order.
+    pub fn segments(&self) -> &[OwnedSegment<V>] {
+        &self.segments
+    }
+
+    /// Writes the formatted segments directly into the given writer, without allocating an
+    /// intermediate `String`.
+    pub fn write_to<W: fmt::Write + ?Sized>(&self, w: &mut W) -> fmt::Result {
+        for segment in &self.segments {
+            write!(w, "{}", segment)?
+        }
+        Ok(())
+    }
+}
+
+impl<V: FormatArgument> fmt::Display for OwnedFormat<V> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.write_to(f)
+    }
+}
+
+/// A format specifier whose width and precision may still refer to arguments.
+#[derive(Debug, Clone, PartialEq)]
+struct UnboundSpecifier {
+    specifier: Specifier,
+    width: Option<ArgumentRef>,
+    precision: Option<ArgumentRef>,
+}
+
+impl UnboundSpecifier {
+    /// Looks up the arguments that supply the width and precision, if any, and returns the
+    /// complete specifier. The width is looked up before the precision, which matters when both
+    /// consume the next positional argument. Fails if either of them exceeds the `limits`.
+    fn bind<V, S>(
+        &self,
+        value_src: &mut S,
+        limits: &FormatLimits,
+    ) -> Result<Specifier, ParseErrorKind>
+    where
+        V: FormatArgument,
+        S: ArgumentSource<V>,
+    {
+        let mut specifier = self.specifier;
+        if let Some(arg) = &self.width {
+            let width = lookup_size(arg, value_src)?;
+            specifier.width = Width::AtLeast { width };
+        }
+        if let Some(arg) = &self.precision {
+            let precision = lookup_size(arg, value_src)?;
+            specifier.precision = Precision::Exactly { precision };
+        }
+        match (specifier.width, specifier.precision) {
+            (Width::AtLeast { width }, _) if width > limits.max_width => {
+                Err(ParseErrorKind::LimitExceeded {
+                    size: width,
+                    limit: limits.max_width,
+                })
+            }
+            (_, Precision::Exactly { precision }) if precision > limits.max_precision => {
+                Err(ParseErrorKind::LimitExceeded {
+                    size: precision,
+                    limit: limits.max_precision,
+                })
+            }
+            _ => Ok(specifier),
+        }
+    }
+}
+
+/// Looks up the argument that supplies a width or precision and tries to convert it to `usize`.
+fn lookup_size<V, S>(arg: &ArgumentRef, value_src: &mut S) -> Result<usize, ParseErrorKind>
+where
+    V: FormatArgument,
+    S: ArgumentSource<V>,
+{
+    let value = match arg {
+        ArgumentRef::Positional(idx) => value_src.lookup_argument_by_index(*idx),
+        ArgumentRef::FromEnd(distance) => lookup_from_end(value_src, *distance),
+        ArgumentRef::Next => value_src.next_argument(),
+        ArgumentRef::Named(name) => value_src.lookup_argument_by_name(name),
+    };
+    let value = match value {
+        Some(value) => value,
+        None => return Err(missing_argument(arg, |limit| count_positional(value_src, limit))),
+    };
+    value
+        .to_usize()
+        .map_err(|_| ParseErrorKind::InvalidSizeArgument)
+}
+
+/// Looks up the positional argument at the given distance from the end. Finds nothing if the
+/// source doesn't know how many positional arguments it has.
+fn lookup_from_end<V, S>(value_src: &S, distance: usize) -> Option<&V>
+where
+    V: FormatArgument,
+    S: ArgumentSource<V> + ?Sized,
+{
+    let idx = value_src.positional_len()?.checked_sub(distance)?;
+    value_src.lookup_argument_by_index(idx)
+}
+
+/// Returns the kind of error to report when the referenced argument doesn't exist. The number of
+/// available positional arguments is only computed if the error needs it, given how many of them
+/// are worth looking up if the source doesn't know.
+fn missing_argument<F>(arg: &ArgumentRef, available: F) -> ParseErrorKind
+where
+    F: FnOnce(usize) -> usize,
+{
+    match arg {
+        ArgumentRef::Positional(index) => ParseErrorKind::ArgumentIndexOutOfRange {
+            index: *index,
+            available: available(*index),
+        },
+        ArgumentRef::FromEnd(distance) => ParseErrorKind::NegativeIndexOutOfRange {
+            distance: *distance,
+            available: available(*distance),
+        },
+        ArgumentRef::Next => {
+            let available = available(0);
+            ParseErrorKind::ArgumentIndexOutOfRange {
+                index: available,
+                available,
+            }
+        }
+        ArgumentRef::Named(name) => ParseErrorKind::MissingNamedArgument { name: name.clone() },
+    }
+}
+
+/// Counts the positional arguments of the given source. If the source doesn't know how many there
+/// are, at most `limit` of them are looked up.
+fn count_positional<V, S>(value_src: &S, limit: usize) -> usize
+where
+    V: FormatArgument,
+    S: ArgumentSource<V> + ?Sized,
+{
+    value_src.positional_len().unwrap_or_else(|| {
+        (0..limit)
+            .take_while(|idx| value_src.lookup_argument_by_index(*idx).is_some())
+            .count()
+    })
+}
+
+/// Finds the given arguments that the placeholders don't use. Named arguments are compared by
+/// the values they resolve to, so that the check agrees with the lookup even if the
+/// `NamedArguments` maps several names to the same value.
+fn unused_arguments<'a, V, P, N>(
+    placeholders: &[Placeholder],
+    positional: &'a P,
+    named: &N,
+) -> Option<ParseErrorKind>
+where
+    V: 'a + FormatArgument,
+    P: PositionalArguments<'a, V> + ?Sized,
+    N: NamedArguments<V> + ?Sized,
+{
+    // Positional arguments that don't know how many there are can't be checked.
+    let positional_len = positional.known_len().unwrap_or(0);
+    let mut used_indices = Vec::new();
+    let mut used_values = Vec::new();
+    let mut next = 0;
+    for arg in placeholders.iter().flat_map(Placeholder::argument_refs) {
+        match arg {
+            ArgumentRef::Next => {
+                used_indices.push(next);
+                next += 1;
+            }
+            ArgumentRef::Positional(idx) => used_indices.push(*idx),
+            ArgumentRef::FromEnd(distance) => {
+                used_indices.extend(positional_len.checked_sub(*distance))
+            }
+            ArgumentRef::Named(name) => {
+                let key = name.split('.').next().unwrap_or(name);
+                used_values.extend(named.get(key).map(|value| value as *const V));
+            }
+        }
+    }
+    // The name a positional argument falls back to, as in `{0|name}`, counts as used too.
+    for name in placeholders.iter().filter_map(|placeholder| placeholder.alias.as_ref()) {
+        let key = name.split('.').next().unwrap_or(name);
+        used_values.extend(named.get(key).map(|value| value as *const V));
+    }
+
+    let unused_positional: Vec<usize> = (0..positional_len)
+        .filter(|idx| !used_indices.contains(idx))
+        .collect();
+    let mut unused_named: Vec<String> = named
+        .names()
+        .into_iter()
+        .filter(|key| match named.get(key) {
+            Some(value) => !used_values.contains(&(value as *const V)),
+            None => false,
+        })
+        .map(str::to_string)
+        .collect();
+    unused_named.sort();
+
+    if unused_positional.is_empty() && unused_named.is_empty() {
+        None
+    } else {
+        Some(ParseErrorKind::UnusedArguments {
+            positional: unused_positional,
+            named: unused_named,
+        })
+    }
+}
+
+/// A substitution in a formatting string that isn't bound to a value yet.
+#[derive(Debug, Clone, PartialEq)]
+struct Placeholder {
+    span: Range<usize>,
+    argument: ArgumentRef,
+    specifier: UnboundSpecifier,
+    /// The range of bytes of the text to use in place of a missing argument, if any.
+    fallback: Option<Range<usize>>,
+    /// The name of the argument to use if the positional one is missing, as in `{0|name}`.
+    alias: Option<String>,
+}
+
+impl Placeholder {
+    /// The references to the arguments the placeholder uses, in the order they are looked up
+    /// when it's bound, starting with the ones that supply width or precision.
+    fn argument_refs(&self) -> impl Iterator<Item = &ArgumentRef> {
+        let spec = &self.specifier;
+        spec.width.iter().chain(&spec.precision).chain(Some(&self.argument))
+    }
+}
+
+/// A single segment of a formatting string that isn't bound to any values yet. The text comes
+/// with the range of bytes it was parsed from, which is longer than the text for an escaped brace.
+#[derive(Debug, Clone, PartialEq)]
+enum TemplateSegment<'s> {
+    Text(&'s str, Range<usize>),
+    Placeholder(Placeholder),
+}
+
+/// The largest width and precision that are allowed, whether they're written in the formatting
+/// string or taken from an argument. Padding and precision make `std::fmt` write that many
+/// characters, so a formatting string from an untrusted source could otherwise make the output
+/// huge, even from a tiny value. By default, there are no limits, like in `format!`, so they have
+/// to be set explicitly, e.g. to `FormatLimits::UNTRUSTED`, when that's a concern.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct FormatLimits {
+    /// The largest width that is allowed.
+    pub max_width: usize,
+    /// The largest precision that is allowed.
+    pub max_precision: usize,
+}
+
+impl FormatLimits {
+    /// Limits that allow any width and precision that fits in `usize`.
+    pub const UNLIMITED: FormatLimits = FormatLimits {
+        max_width: usize::MAX,
+        max_precision: usize::MAX,
+    };
+
+    /// Reasonable limits for a formatting string from an untrusted source, which allow a width
+    /// and precision of up to 4096.
+    pub const UNTRUSTED: FormatLimits = FormatLimits {
+        max_width: 4096,
+        max_precision: 4096,
+    };
+}
+
+impl Default for FormatLimits {
+    fn default() -> Self {
+        FormatLimits::UNLIMITED
+    }
+}
+
+/// The extensions of the `format!` syntax that a formatting string is parsed with. None of them is
+/// enabled by default. A template is parsed with them by `Template::parse_with`,
+/// `Template::parse_all_with` and `OwnedTemplate::parse_with`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Default)]
+pub struct SyntaxOptions {
+    /// Ignores the whitespace around the argument and around the specifier of each substitution,
+    /// see `Parser::with_trimmed_names`.
+    pub trimmed_names: bool,
+    /// Accepts negative argument indices, such as `{-1}` for the last positional argument, see
+    /// `Parser::with_negative_indices`.
+    pub negative_indices: bool,
+}
+
+/// A parsed formatting string that isn't bound to any arguments yet.
+///
+/// Parsing a formatting string once into a `Template` and then binding it to different sets of
+/// arguments avoids repeating the costly part of the parsing for each set of arguments.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Template<'s> {
+    format: &'s str,
+    segments: Vec<TemplateSegment<'s>>,
+    limits: FormatLimits,
+    missing: MissingBehavior,
+    locale: Locale,
+    measure: Measure,
+}
+
+impl<'s> Template<'s> {
+    /// Parses the formatting string. Does not look up any arguments, so it only validates the
+    /// syntax of the formatting string.
+    pub fn parse(format: &'s str) -> Result<Self, ParseError> {
+        Template::parse_with(format, SyntaxOptions::default())
+    }
+
+    /// Like `parse`, but with the given extensions of the syntax, e.g. to accept `{ foo }`.
+    ///
+    /// # Examples
+    /// ```
+    /// use rt_format::parser::SyntaxOptions;
+    /// use rt_format::{NoNamedArguments, StdValue, Template};
+    ///
+    /// let syntax = SyntaxOptions { trimmed_names: true, negative_indices: true };
+    /// let template = Template::parse_with("{ -1 } of { 0 }", syntax).unwrap();
+    /// let args = [StdValue(3), StdValue(5)];
+    /// assert_eq!("5 of 3", template.bind(&args, &NoNamedArguments).unwrap().to_string());
+    /// ```
+    pub fn parse_with(format: &'s str, syntax: SyntaxOptions) -> Result<Self, ParseError> {
+        let segments: Result<Vec<TemplateSegment<'s>>, ParseError> =
+            Scanner::with_syntax(format, syntax).collect();
+        Ok(Template::new(format, segments?))
+    }
+
+    /// Like `parse`, but instead of stopping at the first error, skips the offending brace or
+    /// substitution and keeps going. Returns all the errors, in the order they appear in the
+    /// formatting string.
+    pub fn parse_all(format: &'s str) -> Result<Self, Vec<ParseError>> {
+        Template::parse_all_with(format, SyntaxOptions::default())
+    }
+
+    /// Like `parse_all`, but with the given extensions of the syntax.
+    pub fn parse_all_with(
+        format: &'s str,
+        syntax: SyntaxOptions,
+    ) -> Result<Self, Vec<ParseError>> {
+        let mut segments = Vec::new();
+        let mut errors = Vec::new();
+        for result in Scanner::recovering(format, syntax) {
+            match result {
+                Ok(segment) => segments.push(segment),
+                Err(error) => errors.push(error),
+            }
+        }
+        if errors.is_empty() {
+            Ok(Template::new(format, segments))
+        } else {
+            Err(errors)
+        }
+    }
+
+    /// Creates a template from the segments of the formatting string, with the default options.
+    fn new(format: &'s str, segments: Vec<TemplateSegment<'s>>) -> Self {
+        Template {
+            format,
+            segments,
+            limits: FormatLimits::default(),
+            missing: MissingBehavior::Error,
+            locale: Locale::C,
+            measure: Measure::Chars,
+        }
+    }
+
+    /// Sets the limits on the width and precision that binding the template allows, whether
+    /// they're written in the formatting string or taken from an argument. There are no limits by
+    /// default. A larger width or precision is reported as `ParseErrorKind::LimitExceeded`.
+    pub fn with_limits(mut self, limits: FormatLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// Sets what binding the template does with a substitution whose argument is missing, instead
+    /// of failing.
+    pub fn with_missing_behavior(mut self, missing: MissingBehavior) -> Self {
+        self.missing = missing;
+        self
+    }
+
+    /// Sets the locale of every substitution that binding the template produces, instead of
+    /// `Locale::C`.
+    pub fn with_locale(mut self, locale: Locale) -> Self {
+        self.locale = locale;
+        self
+    }
+
+    /// Sets how the width and the maximum width of every substitution that binding the template
+    /// produces are measured, instead of `Measure::Chars`.
+    pub fn with_measure(mut self, measure: Measure) -> Self {
+        self.measure = measure;
+        self
+    }
+
+    /// Binds the template to the given positional and named arguments. Validates that all the
+    /// arguments are present, and that each argument supports the requested format. Arguments
+    /// that supply width or precision are looked up at this time.
+    pub fn bind<'a, V, P, N>(
+        &self,
+        positional: &'a P,
+        named: &'a N,
+    ) -> Result<ParsedFormat<'a, V>, ParseError>
+    where
+        's: 'a,
+        V: FormatArgument,
+        P: PositionalArguments<'a, V> + ?Sized,
+        N: NamedArguments<V> + ?Sized,
+    {
+        let mut binder = Binder::new(self.format, positional, named, self.limits);
+        binder.missing = self.missing;
+        binder.locale = self.locale;
+        binder.measure = self.measure;
+        let segments: Result<Vec<Segment<'a, V>>, ParseError> = self
+            .segments
+            .iter()
+            .map(|segment| binder.bind(segment))
+            .collect();
+        Ok(ParsedFormat {
+            segments: segments?,
+        })
+    }
+
+    /// Creates a parser that binds the template to the given arguments, with the limits, the
+    /// behavior for missing arguments and the locale of the template. Unlike `bind`, this allows
+    /// setting the other options of `Parser`, such as `Parser::with_unsupported_formatter`, and
+    /// the result borrows the template. Pass the parser to `ParsedFormat::from_parser` to bind.
+    ///
+    /// # Examples
+    /// ```
+    /// use rt_format::prelude::*;
+    ///
+    /// let template = Template::parse("{} {0:x}").unwrap();
+    /// let args: [Primitive; 1] = [1.5.into()];
+    /// let unsupported = |_: &_, _: &_, f: &mut std::fmt::Formatter| f.write_str("<unsupported>");
+    /// let parser = template
+    ///     .parser(&args, &NoNamedArguments)
+    ///     .with_unsupported_formatter(&unsupported);
+    /// assert_eq!("1.5 <unsupported>", ParsedFormat::from_parser(parser).unwrap().to_string());
+    /// ```
+    pub fn parser<'a, V, P, N>(&'a self, positional: &'a P, named: &'a N) -> Parser<'a, V, P, N>
+    where
+        V: FormatArgument,
+        P: PositionalArguments<'a, V> + ?Sized,
+        N: NamedArguments<V> + ?Sized,
+    {
+        let source = ParserSource::Template(self.segments.iter());
+        let options = (self.limits, self.missing, self.locale, self.measure);
+        Parser::for_template(source, self.format, positional, named, options)
+    }
+
+    /// The references to the arguments the template uses, in the order they are looked up when the
+    /// template is bound. Includes the arguments that supply width or precision.
+    fn argument_refs(&self) -> impl Iterator<Item = &ArgumentRef> {
+        self.segments.iter().flat_map(|segment| {
+            let placeholder = match segment {
+                TemplateSegment::Text(..) => None,
+                TemplateSegment::Placeholder(placeholder) => Some(placeholder),
+            };
+            placeholder.into_iter().flat_map(Placeholder::argument_refs)
+        })
+    }
+
+    /// The names of the named arguments the template uses, including the ones that supply width or
+    /// precision. Each name is returned once, in the order of its first use.
+    pub fn required_names(&self) -> impl Iterator<Item = &str> {
+        let mut names: Vec<&str> = Vec::new();
+        for arg in self.argument_refs() {
+            if let ArgumentRef::Named(name) = arg {
+                if !names.contains(&name.as_str()) {
+                    names.push(name);
+                }
+            }
+        }
+        names.into_iter()
+    }
+
+    /// The number of positional arguments the template needs, i.e. one more than the highest index
+    /// it uses. Substitutions without an explicit index, such as `{}` and `.*`, use the positional
+    /// arguments in order. A negative index such as `{-3}` needs at least that many arguments.
+    pub fn positional_count_used(&self) -> usize {
+        let mut next = 0;
+        let mut count = 0;
+        for arg in self.argument_refs() {
+            match arg {
+                ArgumentRef::Next => {
+                    next += 1;
+                    count = count.max(next);
+                }
+                ArgumentRef::Positional(idx) => count = count.max(idx + 1),
+                ArgumentRef::FromEnd(distance) => count = count.max(*distance),
+                ArgumentRef::Named(_) => (),
+            }
+        }
+        count
+    }
+
+    /// The highest index of a positional argument the template uses, or `None` if it doesn't use
+    /// any.
+    pub fn max_positional_index(&self) -> Option<usize> {
+        self.positional_count_used().checked_sub(1)
+    }
+
+    /// Creates an `OwnedTemplate` with a copy of the formatting string, so that it doesn't borrow
+    /// it. The limits, the behavior for missing arguments, the locale and the measure are kept.
+    pub fn to_owned_template(&self) -> OwnedTemplate {
+        OwnedTemplate {
+            format: self.format.to_string(),
+            segments: self.owned_segments(),
+            limits: self.limits,
+            missing: self.missing,
+            locale: self.locale,
+            measure: self.measure,
+        }
+    }
+
+    /// Converts the segments to the ones of an `OwnedTemplate`, replacing each piece of text with
+    /// its position in the formatting string.
+    fn owned_segments(&self) -> Vec<OwnedTemplateSegment> {
+        // Every piece of text is a slice of the formatting string that starts where its span does,
+        // even an escaped brace.
+        self.segments
+            .iter()
+            .map(|segment| match segment {
+                TemplateSegment::Text(text, span) => OwnedTemplateSegment::Text(
+                    span.start..span.start + text.len(),
+                    span.clone(),
+                ),
+                TemplateSegment::Placeholder(placeholder) => {
+                    OwnedTemplateSegment::Placeholder(placeholder.clone())
+                }
             })
+            .collect()
+    }
+
+    /// Checks that the template only uses the named arguments that the signature declares, each
+    /// with one of the formats the signature allows for it. No arguments are needed, so this can
+    /// validate a formatting string before any values exist, e.g. when it's written by a user.
+    ///
+    /// An argument that supplies the width or precision only has to be declared. The names are
+    /// compared as written, so a dotted name such as `{user.name}` has to be declared as a whole.
+    /// Returns all the mismatches, in the order they appear in the formatting string.
+    ///
+    /// # Examples
+    /// ```
+    /// use rt_format::parser::{FormatSignature, SignatureError};
+    /// use rt_format::{Format, Template};
+    ///
+    /// let signature = FormatSignature::new()
+    ///     .with_argument("price", &[Format::Display, Format::LowerExp])
+    ///     .with_argument("count", &[Format::Display, Format::LowerHex]);
+    /// let template = Template::parse("{count:#x} at {price:.2}").unwrap();
+    /// assert_eq!(Ok(()), template.check_against(&signature));
+    ///
+    /// let errors = Template::parse("{price:x} {typo}").unwrap().check_against(&signature);
+    /// assert_eq!(
+    ///     Err(vec![
+    ///         SignatureError::UnsupportedFormat {
+    ///             name: "price".to_string(),
+    ///             format: Format::LowerHex,
+    ///             span: 0..9,
+    ///         },
+    ///         SignatureError::UnknownName { name: "typo".to_string(), span: 10..16 },
+    ///     ]),
+    ///     errors
+    /// );
+    /// ```
+    pub fn check_against(&self, signature: &FormatSignature) -> Result<(), Vec<SignatureError>> {
+        let mut errors = Vec::new();
+        for segment in &self.segments {
+            let placeholder = match segment {
+                TemplateSegment::Text(..) => continue,
+                TemplateSegment::Placeholder(placeholder) => placeholder,
+            };
+            // The width and precision are paired with no format, since they aren't formatted.
+            let spec = &placeholder.specifier;
+            let format = spec.specifier.format;
+            let refs = spec.width.iter().chain(&spec.precision).map(|arg| (arg, None));
+            for (arg, format) in refs.chain(Some((&placeholder.argument, Some(format)))) {
+                let span = placeholder.span.clone();
+                let name = match arg {
+                    ArgumentRef::Named(name) => name,
+                    _ => {
+                        errors.push(SignatureError::PositionalArgument {
+                            argument: arg.clone(),
+                            span,
+                        });
+                        continue;
+                    }
+                };
+                match (signature.formats(name), format) {
+                    (None, _) => errors.push(SignatureError::UnknownName {
+                        name: name.clone(),
+                        span,
+                    }),
+                    (Some(formats), Some(format)) if !formats.contains(&format) => {
+                        errors.push(SignatureError::UnsupportedFormat {
+                            name: name.clone(),
+                            format,
+                            span,
+                        })
+                    }
+                    _ => (),
+                }
+            }
+        }
+        if errors.is_empty() {
+            Ok(())
         } else {
-            Err(())
+            Err(errors)
+        }
+    }
+
+    /// Walks the segments of the template in order, calling the visitor for each of them.
+    pub fn visit(&self, visitor: &mut impl TemplateVisitor) {
+        for segment in &self.segments {
+            match segment {
+                TemplateSegment::Text(text, _) => visitor.literal(text),
+                TemplateSegment::Placeholder(placeholder) => visitor
+                    .placeholder(&placeholder.argument, &placeholder.specifier.specifier),
+            }
+        }
+    }
+
+    /// Calls the given function for each substitution in order, letting it change the argument
+    /// and the specifier in place, e.g. to rename a named argument before binding the template.
+    ///
+    /// Like with `visit`, if the width or precision is taken from an argument, the specifier has
+    /// `Width::Auto` or `Precision::Auto` in its place. Setting it to anything else replaces the
+    /// argument. The spans of the substitutions, as reported in errors, are left as they were.
+    ///
+    /// # Examples
+    /// ```
+    /// use rt_format::{ArgumentRef, Format, NoPositionalArguments, StdValue, Template};
+    /// use std::collections::BTreeMap;
+    ///
+    /// let mut template = Template::parse("{price:x} {count}").unwrap();
+    /// template.map_placeholders(|arg, spec| {
+    ///     if *arg == ArgumentRef::Named("price".to_string()) {
+    ///         *arg = ArgumentRef::Named("total".to_string());
+    ///         spec.format = Format::Display;
+    ///     }
+    /// });
+    ///
+    /// let mut named = BTreeMap::new();
+    /// named.insert("total", StdValue(42));
+    /// named.insert("count", StdValue(3));
+    /// let parsed = template.bind(&NoPositionalArguments, &named).unwrap();
+    /// assert_eq!("42 3", parsed.to_string());
+    /// ```
+    pub fn map_placeholders<F>(&mut self, mut f: F)
+    where
+        F: FnMut(&mut ArgumentRef, &mut Specifier),
+    {
+        for segment in &mut self.segments {
+            let placeholder = match segment {
+                TemplateSegment::Text(..) => continue,
+                TemplateSegment::Placeholder(placeholder) => placeholder,
+            };
+            let spec = &mut placeholder.specifier;
+            f(&mut placeholder.argument, &mut spec.specifier);
+            if spec.specifier.width != Width::Auto {
+                spec.width = None;
+            }
+            if spec.specifier.precision != Precision::Auto {
+                spec.precision = None;
+            }
+        }
+    }
+
+    /// Writes the template back as a formatting string, e.g. to store it after changing it with
+    /// `map_placeholders`. The braces in the text are escaped again, and each substitution is
+    /// written with its specifier in the canonical order, see the `Display` impl of `Specifier`.
+    ///
+    /// Parsing the result gives back an equivalent template, apart from the spans and the
+    /// exceptions listed for `Specifier`. An argument that `map_placeholders` set to something
+    /// that can't be written in a formatting string, such as a width taken from `FromEnd`, is
+    /// written anyway, so the result doesn't parse.
+    ///
+    /// # Examples
+    /// ```
+    /// use rt_format::Template;
+    ///
+    /// let template = Template::parse("{{{0:<+w$.*}}} {name?none:x}").unwrap();
+    /// assert_eq!("{{{0:<+w$.*}}} {name?none:x}", template.to_format_string());
+    /// ```
+    pub fn to_format_string(&self) -> String {
+        let mut result = String::with_capacity(self.format.len());
+        for segment in &self.segments {
+            match segment {
+                TemplateSegment::Text(text, _) => {
+                    for c in text.chars() {
+                        if c == '{' || c == '}' {
+                            result.push(c);
+                        }
+                        result.push(c);
+                    }
+                }
+                TemplateSegment::Placeholder(placeholder) => {
+                    self.write_placeholder(&mut result, placeholder)
+                }
+            }
+        }
+        result
+    }
+
+    /// Writes a substitution the way it's written in a formatting string, with its alias and
+    /// fallback after the argument.
+    fn write_placeholder(&self, out: &mut String, placeholder: &Placeholder) {
+        let mut argument = placeholder.argument.to_string();
+        if let Some(alias) = &placeholder.alias {
+            argument.push('|');
+            argument.push_str(alias);
+        }
+        if let Some(fallback) = &placeholder.fallback {
+            argument.push('?');
+            argument.push_str(&self.format[fallback.clone()]);
+        }
+        let unbound = &placeholder.specifier;
+        // Writing to a `String` doesn't fail.
+        let _ = unbound.specifier.write_placeholder(
+            out,
+            &argument,
+            unbound.width.as_ref(),
+            unbound.precision.as_ref(),
+        );
+    }
+}
+
+/// A segment of an `OwnedTemplate`, with the literal text given by its position in the formatting
+/// string instead of a slice of it, followed by the range of bytes it was parsed from.
+#[derive(Debug, Clone, PartialEq)]
+enum OwnedTemplateSegment {
+    Text(Range<usize>, Range<usize>),
+    Placeholder(Placeholder),
+}
+
+/// A `Template` that owns its formatting string, so it can be stored, e.g. in a struct, without
+/// keeping the string it was parsed from around. It's meant for formatting strings that are
+/// loaded at runtime, parsed once, and then bound to many sets of arguments.
+///
+/// # Examples
+/// ```
+/// use rt_format::{NoNamedArguments, OwnedTemplate, StdValue};
+///
+/// struct Report {
+///     template: OwnedTemplate,
+/// }
+///
+/// let loaded = String::from("{} of {}");
+/// let report = Report { template: OwnedTemplate::parse(loaded).unwrap() };
+/// let args = [StdValue(3), StdValue(5)];
+/// let parsed = report.template.bind(&args, &NoNamedArguments).unwrap();
+/// assert_eq!("3 of 5", parsed.to_string());
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct OwnedTemplate {
+    format: String,
+    segments: Vec<OwnedTemplateSegment>,
+    limits: FormatLimits,
+    missing: MissingBehavior,
+    locale: Locale,
+    measure: Measure,
+}
+
+impl OwnedTemplate {
+    /// Parses the formatting string, taking ownership of it. Like `Template::parse`, it only
+    /// validates the syntax of the formatting string.
+    pub fn parse(format: impl Into<String>) -> Result<Self, ParseError> {
+        OwnedTemplate::parse_with(format, SyntaxOptions::default())
+    }
+
+    /// Like `parse`, but with the given extensions of the syntax, like `Template::parse_with`.
+    pub fn parse_with(
+        format: impl Into<String>,
+        syntax: SyntaxOptions,
+    ) -> Result<Self, ParseError> {
+        let format = format.into();
+        let template = Template::parse_with(&format, syntax)?;
+        let segments = template.owned_segments();
+        Ok(OwnedTemplate {
+            segments,
+            limits: template.limits,
+            missing: template.missing,
+            locale: template.locale,
+            measure: template.measure,
+            format,
+        })
+    }
+
+    /// The formatting string the template was parsed from.
+    pub fn as_str(&self) -> &str {
+        &self.format
+    }
+
+    /// Binds the template to the given positional and named arguments, the same way
+    /// `Template::bind` does. The literal text of the result borrows from the template.
+    pub fn bind<'a, V, P, N>(
+        &'a self,
+        positional: &'a P,
+        named: &'a N,
+    ) -> Result<ParsedFormat<'a, V>, ParseError>
+    where
+        V: FormatArgument,
+        P: PositionalArguments<'a, V> + ?Sized,
+        N: NamedArguments<V> + ?Sized,
+    {
+        ParsedFormat::from_parser(self.parser(positional, named))
+    }
+
+    /// Creates a parser that binds the template to the given arguments, like `Template::parser`.
+    pub fn parser<'a, V, P, N>(&'a self, positional: &'a P, named: &'a N) -> Parser<'a, V, P, N>
+    where
+        V: FormatArgument,
+        P: PositionalArguments<'a, V> + ?Sized,
+        N: NamedArguments<V> + ?Sized,
+    {
+        let source = ParserSource::OwnedTemplate(&self.format, self.segments.iter());
+        let options = (self.limits, self.missing, self.locale, self.measure);
+        Parser::for_template(source, &self.format, positional, named, options)
+    }
+}
+
+/// The named arguments that a formatting string may use, and the formats each of them supports,
+/// for checking a `Template` with `Template::check_against`.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct FormatSignature {
+    arguments: BTreeMap<String, Vec<Format>>,
+}
+
+impl FormatSignature {
+    /// Creates a signature without any arguments.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Declares the named argument, with the formats it supports. Declaring the same name again
+    /// replaces its formats.
+    pub fn with_argument(mut self, name: impl Into<String>, formats: &[Format]) -> Self {
+        self.arguments.insert(name.into(), formats.to_vec());
+        self
+    }
+
+    /// The formats that the named argument supports, or `None` if it isn't declared.
+    pub fn formats(&self, name: &str) -> Option<&[Format]> {
+        self.arguments.get(name).map(Vec::as_slice)
+    }
+}
+
+/// A way in which a `Template` doesn't match a `FormatSignature`. Each variant has the span of the
+/// offending substitution in the formatting string, including the braces.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SignatureError {
+    /// The template uses a named argument that the signature doesn't declare.
+    UnknownName {
+        /// The name of the argument.
+        name: String,
+        /// The range of bytes of the substitution.
+        span: Range<usize>,
+    },
+    /// The template formats a named argument with a format that the signature doesn't allow for it.
+    UnsupportedFormat {
+        /// The name of the argument.
+        name: String,
+        /// The requested format.
+        format: Format,
+        /// The range of bytes of the substitution.
+        span: Range<usize>,
+    },
+    /// The template uses a positional argument, which a signature can't declare.
+    PositionalArgument {
+        /// The reference to the argument.
+        argument: ArgumentRef,
+        /// The range of bytes of the substitution.
+        span: Range<usize>,
+    },
+}
+
+impl SignatureError {
+    /// The range of bytes in the formatting string of the substitution that caused the error.
+    pub fn span(&self) -> Range<usize> {
+        match self {
+            SignatureError::UnknownName { span, .. }
+            | SignatureError::UnsupportedFormat { span, .. }
+            | SignatureError::PositionalArgument { span, .. } => span.clone(),
+        }
+    }
+}
+
+impl fmt::Display for SignatureError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SignatureError::UnknownName { name, .. } => write!(f, "unknown argument `{}`", name)?,
+            SignatureError::UnsupportedFormat { name, format, .. } => {
+                write!(f, "argument `{}` does not support the format `:{}`", name, format)?
+            }
+            SignatureError::PositionalArgument { .. } => f.write_str("positional argument")?,
+        }
+        write!(f, " at offset {}", self.span().start)
+    }
+}
+
+#[cfg(feature = "std")]
+impl error::Error for SignatureError {}
+
+/// Receives the segments of a `Template`, as passed to `Template::visit`.
+pub trait TemplateVisitor {
+    /// Called for the text between the substitutions. An escaped brace is passed as a separate
+    /// literal that contains just the brace.
+    fn literal(&mut self, text: &str);
+
+    /// Called for each substitution, with the reference to its argument and its specifier. If the
+    /// width or precision is taken from an argument, the specifier has `Width::Auto` or
+    /// `Precision::Auto` in its place.
+    fn placeholder(&mut self, arg: &ArgumentRef, spec: &Specifier);
+}
+
+/// A specifier component that can be parsed from the corresponding part of the formatting string.
+trait Parseable<'m>
+where
+    Self: Sized,
+{
+    fn parse(capture: Option<&'m str>) -> Result<Self, ParseErrorKind>;
+}
+
+impl<'m, T> Parseable<'m> for T
+where
+    T: Sized + TryFrom<&'m str, Error = ()>,
+{
+    fn parse(capture: Option<&'m str>) -> Result<Self, ParseErrorKind> {
+        capture
+            .unwrap_or("")
+            .try_into()
+            .map_err(|_| ParseErrorKind::InvalidSpecifier)
+    }
+}
+
+/// Parses a size specifier, such as width or precision. If the size is not hard-coded in the
+/// formatting string, returns the reference to the argument that supplies it.
+fn parse_size(text: &str) -> Result<Result<usize, ArgumentRef>, ParseErrorKind> {
+    if text == "*" {
+        Ok(Err(ArgumentRef::Next))
+    } else if let Some(text) = text.strip_suffix('$') {
+        if text.as_bytes()[0].is_ascii_digit() {
+            text.parse()
+                .map(|idx| Err(ArgumentRef::Positional(idx)))
+                .map_err(|_| ParseErrorKind::InvalidSpecifier)
+        } else {
+            Ok(Err(ArgumentRef::Named(text.to_string())))
+        }
+    } else {
+        // The text is all digits, so it can only fail to parse if it's too large.
+        text.parse()
+            .map(Ok)
+            .map_err(|_| ParseErrorKind::WidthOverflow)
+    }
+}
+
+/// A position in the text of a single substitution, which the scanning functions move forward as
+/// they recognize the parts of the substitution.
+struct Cursor<'s> {
+    text: &'s str,
+    pos: usize,
+}
+
+impl<'s> Cursor<'s> {
+    fn new(text: &'s str) -> Self {
+        Cursor { text, pos: 0 }
+    }
+
+    fn rest(&self) -> &'s str {
+        &self.text[self.pos..]
+    }
+
+    fn take(&mut self, len: usize) -> &'s str {
+        let taken = &self.text[self.pos..self.pos + len];
+        self.pos += len;
+        taken
+    }
+
+    /// Consumes the next character if it satisfies the predicate.
+    fn eat_if(&mut self, pred: impl Fn(char) -> bool) -> Option<&'s str> {
+        match self.rest().chars().next() {
+            Some(c) if pred(c) => Some(self.take(c.len_utf8())),
+            _ => None,
+        }
+    }
+
+    /// Consumes the next character if it is the given one.
+    fn eat(&mut self, expected: char) -> Option<&'s str> {
+        self.eat_if(|c| c == expected)
+    }
+
+    /// Consumes any whitespace at the start of the unconsumed text.
+    fn skip_whitespace(&mut self) {
+        self.take(self.count_while(0, char::is_whitespace));
+    }
+
+    /// Returns the length of the longest run of characters that satisfy the predicate, starting
+    /// `skip` bytes into the unconsumed text. Nothing is consumed.
+    fn count_while(&self, skip: usize, pred: impl Fn(char) -> bool) -> usize {
+        let rest = &self.rest()[skip..];
+        rest.find(|c| !pred(c)).unwrap_or(rest.len())
+    }
+}
+
+/// The parts of a format specifier, as they appear in the formatting string.
+#[derive(Default)]
+struct SpecifierParts<'s> {
+    fill: Option<&'s str>,
+    align: Option<&'s str>,
+    sign: Option<&'s str>,
+    repr: Option<&'s str>,
+    pad: Option<&'s str>,
+    width: Option<&'s str>,
+    grouping: Option<&'s str>,
+    max_width: Option<&'s str>,
+    precision: Option<&'s str>,
+    format: Option<&'s str>,
+}
+
+/// Returns true if the character can start an argument name. Names follow the same rules as Rust
+/// identifiers.
+fn is_name_start(c: char) -> bool {
+    c == '_' || UnicodeXID::is_xid_start(c)
+}
+
+fn is_align(c: char) -> bool {
+    matches!(c, '<' | '^' | '>')
+}
+
+/// Scans an argument name, which may be a path of names separated by `.`, such as `user.name`.
+fn scan_name<'s>(cursor: &mut Cursor<'s>) -> Option<&'s str> {
+    if !cursor.rest().starts_with(is_name_start) {
+        return None;
+    }
+    let start = cursor.pos;
+    loop {
+        cursor.take(cursor.count_while(0, UnicodeXID::is_xid_continue));
+        let mut chars = cursor.rest().chars();
+        match (chars.next(), chars.next()) {
+            (Some('.'), Some(c)) if is_name_start(c) => {
+                cursor.take(1);
+            }
+            _ => break,
         }
     }
+    Some(&cursor.text[start..cursor.pos])
+}
 
-    /// A reference to the formatting specifier.
-    pub fn specifier(&self) -> &Specifier {
-        &self.specifier
+/// Scans a width or precision: a number, optionally followed by `$`, or a name followed by `$`. If
+/// `allow_next` is true, the `*` is also accepted.
+fn scan_size<'s>(cursor: &mut Cursor<'s>, allow_next: bool) -> Option<&'s str> {
+    let digits = cursor.count_while(0, |c| c.is_ascii_digit());
+    if digits > 0 {
+        let dollar = cursor.rest()[digits..].starts_with('$');
+        return Some(cursor.take(digits + dollar as usize));
+    }
+    if cursor.rest().starts_with(is_name_start) {
+        let len = cursor.count_while(0, UnicodeXID::is_xid_continue);
+        if cursor.rest()[len..].starts_with('$') {
+            return Some(cursor.take(len + 1));
+        }
+        return None;
+    }
+    if allow_next {
+        cursor.eat('*')
+    } else {
+        None
     }
+}
 
-    /// A reference to the value to format.
-    pub fn value(&self) -> &'v V {
-        self.value
+/// Scans as much of a format specifier as possible. Whatever follows the specifier is left for the
+/// caller to check.
+fn scan_specifier<'s>(cursor: &mut Cursor<'s>) -> SpecifierParts<'s> {
+    let mut parts = SpecifierParts::default();
+
+    let mut chars = cursor.rest().chars();
+    match (chars.next(), chars.next()) {
+        (Some(fill), Some(align)) if is_align(align) && fill != '{' && fill != '}' => {
+            parts.fill = Some(cursor.take(fill.len_utf8()));
+            parts.align = Some(cursor.take(1));
+        }
+        (Some(align), _) if is_align(align) => parts.align = Some(cursor.take(1)),
+        _ => (),
+    }
+    parts.sign = cursor.eat('+');
+    parts.repr = cursor.eat('#');
+    // In `0$`, the zero is the index of the width argument rather than the zero-padding flag.
+    if !cursor.rest().starts_with("0$") {
+        parts.pad = cursor.eat('0');
     }
+    parts.width = scan_size(cursor, false);
+    parts.grouping = cursor.eat_if(|c| matches!(c, ',' | '_' | ' '));
+    if let Some(marker) = cursor.rest().chars().next().filter(|c| matches!(c, '~' | '…')) {
+        let digits = cursor.count_while(marker.len_utf8(), |c| c.is_ascii_digit());
+        if digits > 0 {
+            parts.max_width = Some(cursor.take(marker.len_utf8() + digits));
+        }
+    }
+    if cursor.rest().starts_with('.') {
+        let dot = cursor.pos;
+        cursor.take(1);
+        parts.precision = scan_size(cursor, true);
+        if parts.precision.is_none() {
+            cursor.pos = dot;
+        }
+    }
+    // Letters other than the standard ones select a custom format.
+    parts.format = cursor.eat_if(|c| c == '?' || c.is_ascii_alphabetic());
+
+    parts
 }
 
-impl<'v, V: FormatArgument> fmt::Display for Substitution<'v, V> {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        format_value(&self.specifier, &ArgumentFormatter(self.value), f)
+/// Parses a maximum width, such as `~8` or `…8`.
+fn parse_max_width(text: &str) -> Result<MaxWidth, ParseErrorKind> {
+    let parse = |digits: &str| digits.parse().map_err(|_| ParseErrorKind::WidthOverflow);
+    if let Some(digits) = text.strip_prefix('~') {
+        parse(digits).map(|width| MaxWidth::Cut { width })
+    } else if let Some(digits) = text.strip_prefix('…') {
+        parse(digits).map(|width| MaxWidth::Ellipsis { width })
+    } else {
+        Err(ParseErrorKind::InvalidSpecifier)
     }
 }
 
-/// A single segment of a formatting string.
-#[derive(Debug, Copy, Clone, PartialEq)]
-pub enum Segment<'s, V: FormatArgument> {
-    /// Text to be sent to the formatter.
-    Text(&'s str),
-    /// A value ready to be formatted.
-    Substitution(Substitution<'s, V>),
+fn parse_specifier_parts(parts: &SpecifierParts) -> Result<UnboundSpecifier, ParseErrorKind> {
+    let fill = parts.fill.and_then(|s| s.chars().next());
+    let pad = Pad::parse(parts.pad)?;
+
+    let (width, width_arg) = match parts.width.map(parse_size) {
+        None => (Width::Auto, None),
+        Some(Ok(Ok(width))) => (Width::AtLeast { width }, None),
+        Some(Ok(Err(arg))) => (Width::Auto, Some(arg)),
+        Some(Err(kind)) => return Err(kind),
+    };
+    let max_width = match parts.max_width.map(parse_max_width) {
+        None => MaxWidth::Unlimited,
+        Some(Ok(max_width)) => max_width,
+        Some(Err(kind)) => return Err(kind),
+    };
+    let (precision, precision_arg) = match parts.precision.map(parse_size) {
+        None => (Precision::Auto, None),
+        Some(Ok(Ok(precision))) => (Precision::Exactly { precision }, None),
+        Some(Ok(Err(arg))) => (Precision::Auto, Some(arg)),
+        Some(Err(kind)) => return Err(kind),
+    };
+
+    Ok(UnboundSpecifier {
+        specifier: Specifier {
+            fill,
+            align: Align::parse(parts.align)?,
+            sign: Sign::parse(parts.sign)?,
+            repr: Repr::parse(parts.repr)?,
+            pad,
+            width,
+            grouping: Grouping::parse(parts.grouping)?,
+            max_width,
+            locale: Locale::C,
+            measure: Measure::Chars,
+            precision,
+            format: Format::parse(parts.format)?,
+        },
+        width: width_arg,
+        precision: precision_arg,
+    })
 }
 
-impl<'s, V: FormatArgument> fmt::Display for Segment<'s, V> {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        match self {
-            Segment::Text(text) => f.write_str(text),
-            Segment::Substitution(arg) => arg.fmt(f),
-        }
+/// Parses only the format specifier portion of a format argument. For example, in a format
+/// argument specification `{foo:#X}`, this function would parse only the `#X` part.
+pub fn parse_specifier<V, S>(spec_str: &str, value_src: &mut S) -> Result<Specifier, ParseError>
+where
+    V: FormatArgument,
+    S: ArgumentSource<V>,
+{
+    let (_, unbound) = parse_whole_specifier(spec_str)?;
+    unbound
+        .bind(value_src, &FormatLimits::default())
+        .map_err(|kind| ParseError::new(0, kind))
+}
+
+/// Parses a format specifier that doesn't need any arguments, such as the `#X` in `{foo:#X}`. A
+/// width or precision that refers to an argument, as in `1$` or `.*`, is reported as
+/// `ParseErrorKind::RequiresArgument`.
+///
+/// # Examples
+/// ```
+/// use rt_format::parser::{parse_specifier_str, ParseErrorKind};
+/// use rt_format::ArgumentRef;
+///
+/// assert_eq!("#010X", parse_specifier_str("#010X").unwrap().to_string());
+/// assert_eq!(
+///     &ParseErrorKind::RequiresArgument { argument: ArgumentRef::Positional(1) },
+///     parse_specifier_str("1$").unwrap_err().kind()
+/// );
+/// ```
+pub fn parse_specifier_str(spec_str: &str) -> Result<Specifier, ParseError> {
+    let (_, unbound) = parse_whole_specifier(spec_str)?;
+    match unbound.width.or(unbound.precision) {
+        Some(argument) => Err(ParseError::new(0, ParseErrorKind::RequiresArgument { argument })),
+        None => Ok(unbound.specifier),
     }
 }
 
-/// A representation of the formatting string and associated values, ready to be formatted.
-#[derive(Debug, Clone, PartialEq)]
-pub struct ParsedFormat<'a, V: FormatArgument> {
-    /// A vector of formatting string segments.
-    pub segments: Vec<Segment<'a, V>>,
+/// Scans and parses the given string, which has to consist of a format specifier and nothing else.
+fn parse_whole_specifier(
+    spec_str: &str,
+) -> Result<(SpecifierParts<'_>, UnboundSpecifier), ParseError> {
+    let mut cursor = Cursor::new(spec_str);
+    let parts = scan_specifier(&mut cursor);
+    if !cursor.rest().is_empty() {
+        return Err(ParseError::new(0, ParseErrorKind::InvalidSpecifier));
+    }
+    let unbound = parse_specifier_parts(&parts).map_err(|kind| ParseError::new(0, kind))?;
+    Ok((parts, unbound))
 }
 
-impl<'a, V: FormatArgument> ParsedFormat<'a, V> {
-    /// Parses the formatting string, using given positional and named arguments. Does not perform
-    /// any formatting. It just parses the formatting string, validates that all the arguments are
-    /// present, and that each argument supports the requested format.
-    pub fn parse<P, N>(format: &'a str, positional: &'a P, named: &'a N) -> Result<Self, usize>
-    where
-        P: PositionalArguments<'a, V> + ?Sized,
-        N: NamedArguments<V>,
-    {
-        let segments: Result<Vec<Segment<'a, V>>, usize> =
-            Parser::new(format, positional, named).collect();
-        Ok(ParsedFormat {
-            segments: segments?,
-        })
+/// Parses a format specifier on its own, such as the `#X` in `{foo:#X}`. This is the same as
+/// `parse_specifier_str`.
+impl FromStr for Specifier {
+    type Err = ParseError;
+
+    fn from_str(spec_str: &str) -> Result<Self, Self::Err> {
+        parse_specifier_str(spec_str)
     }
 }
 
-impl<'a, V: FormatArgument> fmt::Display for ParsedFormat<'a, V> {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        for segment in &self.segments {
-            segment.fmt(f)?
+/// Parses a partial format specifier. Each part is set only if it's written in the string. As with
+/// `Specifier`, a width or precision that refers to an argument fails with
+/// `ParseErrorKind::RequiresArgument`.
+impl FromStr for PartialSpecifier {
+    type Err = ParseError;
+
+    fn from_str(spec_str: &str) -> Result<Self, Self::Err> {
+        let (parts, unbound) = parse_whole_specifier(spec_str)?;
+        if let Some(argument) = unbound.width.or(unbound.precision) {
+            return Err(ParseError::new(0, ParseErrorKind::RequiresArgument { argument }));
         }
-        Ok(())
+        let spec = unbound.specifier;
+        Ok(PartialSpecifier {
+            fill: spec.fill,
+            align: parts.align.map(|_| spec.align),
+            sign: parts.sign.map(|_| spec.sign),
+            repr: parts.repr.map(|_| spec.repr),
+            pad: parts.pad.map(|_| spec.pad),
+            width: parts.width.map(|_| spec.width),
+            grouping: parts.grouping.map(|_| spec.grouping),
+            max_width: parts.max_width.map(|_| spec.max_width),
+            precision: parts.precision.map(|_| spec.precision),
+            format: parts.format.map(|_| spec.format),
+        })
     }
 }
 
-/// A specifier component that can be parsed from the corresponding part of the formatting string.
-trait Parseable<'m, V, S>
-where
-    V: FormatArgument,
-    S: ArgumentSource<V>,
-    Self: Sized,
-{
-    fn parse(capture: Option<Match<'m>>, value_src: &mut S) -> Result<Self, ()>;
+/// An iterator of `TemplateSegment`s that correspond to the parts of the formatting string being
+/// scanned.
+struct Scanner<'s> {
+    unparsed: &'s str,
+    parsed_len: usize,
+    recover: bool,
+    trim: bool,
+    negative: bool,
 }
 
-impl<'m, V, S, T> Parseable<'m, V, S> for T
-where
-    V: FormatArgument,
-    S: ArgumentSource<V>,
-    T: Sized + TryFrom<&'m str, Error = ()>,
-{
-    fn parse(capture: Option<Match<'m>>, _: &mut S) -> Result<Self, ()> {
-        capture.map(|m| m.as_str()).unwrap_or("").try_into()
+impl<'s> Scanner<'s> {
+    fn new(format: &'s str) -> Self {
+        Scanner {
+            unparsed: format,
+            parsed_len: 0,
+            recover: false,
+            trim: false,
+            negative: false,
+        }
     }
-}
 
-/// Parses a size specifier, such as width or precision. If the size is not hard-coded in the
-/// formatting string, looks up the corresponding argument and tries to convert it to `usize`.
-fn parse_size<'m, V, S>(text: &str, value_src: &S) -> Result<usize, ()>
-where
-    V: FormatArgument,
-    S: ArgumentSource<V>,
-{
-    if text.ends_with('$') {
-        let text = &text[..text.len() - 1];
-        let value = if text.as_bytes()[0].is_ascii_digit() {
-            text.parse()
-                .ok()
-                .and_then(|idx| value_src.lookup_argument_by_index(idx))
+    /// Creates a scanner that accepts the given extensions of the syntax.
+    fn with_syntax(format: &'s str, syntax: SyntaxOptions) -> Self {
+        Scanner {
+            trim: syntax.trimmed_names,
+            negative: syntax.negative_indices,
+            ..Scanner::new(format)
+        }
+    }
+
+    /// Creates a scanner that keeps going after an error, by skipping the offending brace or
+    /// substitution.
+    fn recovering(format: &'s str, syntax: SyntaxOptions) -> Self {
+        Scanner {
+            recover: true,
+            ..Scanner::with_syntax(format, syntax)
+        }
+    }
+
+    fn advance_and_return<T>(&mut self, advance_by: usize, result: T) -> T {
+        self.unparsed = &self.unparsed[advance_by..];
+        self.parsed_len += advance_by;
+        result
+    }
+
+    fn stop(&mut self) {
+        self.unparsed = "";
+    }
+
+    fn error(&mut self, kind: ParseErrorKind) -> Result<TemplateSegment<'s>, ParseError> {
+        let len = match kind {
+            ParseErrorKind::UnmatchedBrace => 1,
+            _ => self.unparsed.find('}').map_or(self.unparsed.len(), |idx| idx + 1),
+        };
+        let error = ParseError::with_span(self.parsed_len..self.parsed_len + len, kind);
+        if !self.recover {
+            self.stop();
+        } else if self.unparsed.starts_with('{') {
+            let len = self.unparsed.find('}').map_or(self.unparsed.len(), |idx| idx + 1);
+            self.advance_and_return(len, ());
+        } else {
+            self.advance_and_return(1, ());
+        }
+        Err(error)
+    }
+
+    fn text_segment(&mut self, len: usize) -> TemplateSegment<'s> {
+        let span = self.parsed_len..self.parsed_len + len;
+        self.advance_and_return(len, TemplateSegment::Text(&self.unparsed[..len], span))
+    }
+
+    fn parse_braces(&mut self) -> Result<TemplateSegment<'s>, ParseError> {
+        if self.unparsed.len() < 2 {
+            self.error(ParseErrorKind::UnmatchedBrace)
+        } else if self.unparsed.as_bytes()[0] == self.unparsed.as_bytes()[1] {
+            let span = self.parsed_len..self.parsed_len + 2;
+            Ok(self.advance_and_return(2, TemplateSegment::Text(&self.unparsed[..1], span)))
+        } else if self.unparsed.starts_with('}') {
+            self.error(ParseErrorKind::UnmatchedBrace)
         } else {
-            value_src.lookup_argument_by_name(text)
+            self.parse_substitution()
+        }
+    }
+
+    fn parse_substitution(&mut self) -> Result<TemplateSegment<'s>, ParseError> {
+        let mut cursor = Cursor::new(self.unparsed);
+        cursor.take(1);
+        if self.trim {
+            cursor.skip_whitespace();
+        }
+
+        let sign_len = match cursor.rest().as_bytes() {
+            [b'-', digit, ..] if self.negative && digit.is_ascii_digit() => 1,
+            _ => 0,
+        };
+        let index_len = cursor.count_while(sign_len, |c| c.is_ascii_digit());
+        let index = if index_len > 0 { Some(cursor.take(sign_len + index_len)) } else { None };
+        let name = match index {
+            None => scan_name(&mut cursor),
+            Some(_) => None,
         };
-        value.ok_or(()).and_then(FormatArgument::to_usize)
+        let alias = match index {
+            Some(_) if cfg!(feature = "index-or-name") && cursor.rest().starts_with('|') => {
+                let bar = cursor.pos;
+                cursor.take(1);
+                let alias = scan_name(&mut cursor);
+                if alias.is_none() {
+                    cursor.pos = bar;
+                }
+                alias
+            }
+            _ => None,
+        };
+        if self.trim {
+            cursor.skip_whitespace();
+        }
+        let fallback = match cursor.eat('?') {
+            Some(_) => {
+                let mut start = cursor.pos;
+                let len = cursor.count_while(0, |c| !matches!(c, ':' | '{' | '}'));
+                let mut text = cursor.take(len);
+                if self.trim {
+                    start += text.len() - text.trim_start().len();
+                    text = text.trim();
+                }
+                Some(self.parsed_len + start..self.parsed_len + start + text.len())
+            }
+            None => None,
+        };
+        let parts = match cursor.eat(':') {
+            Some(_) if self.trim => {
+                // Scans the specifier on its own, so that a trailing space isn't taken for the
+                // grouping separator. If it's malformed, the brace check below reports it.
+                let len = cursor.rest().find('}').unwrap_or(cursor.rest().len());
+                let mut spec_cursor = Cursor::new(cursor.rest()[..len].trim());
+                let parts = scan_specifier(&mut spec_cursor);
+                if spec_cursor.rest().is_empty() {
+                    cursor.take(len);
+                }
+                parts
+            }
+            Some(_) => scan_specifier(&mut cursor),
+            None => SpecifierParts::default(),
+        };
+
+        if cursor.eat('}').is_none() {
+            return if self.unparsed.contains('}') {
+                self.error(ParseErrorKind::InvalidSpecifier)
+            } else {
+                self.error(ParseErrorKind::UnmatchedBrace)
+            };
+        }
+
+        let result = parse_specifier_parts(&parts).and_then(|specifier| {
+            Ok(Placeholder {
+                span: self.parsed_len..self.parsed_len + cursor.pos,
+                argument: parse_argument_ref(index, name)?,
+                specifier,
+                fallback,
+                alias: alias.map(str::to_string),
+            })
+        });
+        match result {
+            Ok(placeholder) => Ok(self.advance_and_return(
+                cursor.pos,
+                TemplateSegment::Placeholder(placeholder),
+            )),
+            Err(kind) => self.error(kind),
+        }
+    }
+}
+
+fn parse_argument_ref(
+    index: Option<&str>,
+    name: Option<&str>,
+) -> Result<ArgumentRef, ParseErrorKind> {
+    if let Some(distance) = index.and_then(|idx| idx.strip_prefix('-')) {
+        match distance.parse() {
+            Ok(0) | Err(_) => Err(ParseErrorKind::InvalidSpecifier),
+            Ok(distance) => Ok(ArgumentRef::FromEnd(distance)),
+        }
+    } else if let Some(idx) = index {
+        idx.parse()
+            .map(ArgumentRef::Positional)
+            .map_err(|_| ParseErrorKind::InvalidSpecifier)
+    } else if let Some(name) = name {
+        Ok(ArgumentRef::Named(name.to_string()))
     } else {
-        text.parse().map_err(|_| ())
+        Ok(ArgumentRef::Next)
     }
 }
 
-impl<'m, V, S> Parseable<'m, V, S> for Width
-where
-    V: FormatArgument,
-    S: ArgumentSource<V>,
-{
-    fn parse(capture: Option<Match<'m>>, value_src: &mut S) -> Result<Self, ()> {
-        match capture.map(|m| m.as_str()).unwrap_or("") {
-            "" => Ok(Width::Auto),
-            s @ _ => parse_size(s, value_src).map(|width| Width::AtLeast { width }),
+impl<'s> Iterator for Scanner<'s> {
+    type Item = Result<TemplateSegment<'s>, ParseError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        static BRACES: &[char] = &['{', '}'];
+
+        if self.unparsed.is_empty() {
+            return None;
+        }
+
+        match self.unparsed.find(BRACES) {
+            None => Some(Ok(self.text_segment(self.unparsed.len()))),
+            Some(0) => Some(self.parse_braces()),
+            Some(brace_idx) => Some(Ok(self.text_segment(brace_idx))),
         }
     }
 }
 
-impl<'m, V, S> Parseable<'m, V, S> for Precision
+/// Binds `TemplateSegment`s to the given positional and named arguments.
+struct Binder<'p, V, P, N>
 where
     V: FormatArgument,
-    S: ArgumentSource<V>,
+    P: PositionalArguments<'p, V> + ?Sized,
+    N: NamedArguments<V> + ?Sized,
 {
-    fn parse(capture: Option<Match<'m>>, value_src: &mut S) -> Result<Self, ()> {
-        match capture.map(|m| m.as_str()).unwrap_or("") {
-            "" => Ok(Precision::Auto),
-            "*" => value_src
-                .next_argument()
-                .ok_or(())
-                .and_then(FormatArgument::to_usize)
-                .map(|precision| Precision::Exactly { precision }),
-            s @ _ => parse_size(s, value_src).map(|precision| Precision::Exactly { precision }),
-        }
-    }
-}
-
-macro_rules! SPEC_REGEX_FRAG {
-    () => { r"
-        (?P<align>[<^>])?
-        (?P<sign>\+)?
-        (?P<repr>\#)?
-        (?P<pad>0)?
-        (?P<width>
-            (?:\d+\$?)|(?:[[:alpha:]][[:alnum:]]*\$)
-        )?
-        (?:\.(?P<precision>
-            (?:\d+\$?)|(?:[[:alpha:]][[:alnum:]]*\$)|\*
-        ))?
-        (?P<format>[?oxXbeE])?
-    " };
-}
-
-fn parse_specifier_captures<V, S>(captures: &Captures, value_src: &mut S) -> Result<Specifier, ()>
+    format: &'p str,
+    positional: &'p P,
+    named: &'p N,
+    positional_iter: P::Iter,
+    next_index: usize,
+    exhausted: bool,
+    limits: FormatLimits,
+    missing: MissingBehavior,
+    locale: Locale,
+    measure: Measure,
+    unsupported: Option<&'p UnsupportedFormatter<V>>,
+}
+
+impl<'p, V, P, N> Binder<'p, V, P, N>
 where
     V: FormatArgument,
-    S: ArgumentSource<V>,
+    P: PositionalArguments<'p, V> + ?Sized,
+    N: NamedArguments<V> + ?Sized,
 {
-    Ok(Specifier {
-        align: Align::parse(captures.name("align"), value_src)?,
-        sign: Sign::parse(captures.name("sign"), value_src)?,
-        repr: Repr::parse(captures.name("repr"), value_src)?,
-        pad: Pad::parse(captures.name("pad"), value_src)?,
-        width: Width::parse(captures.name("width"), value_src)?,
-        precision: Precision::parse(captures.name("precision"), value_src)?,
-        format: Format::parse(captures.name("format"), value_src)?,
-    })
+    fn new(format: &'p str, positional: &'p P, named: &'p N, limits: FormatLimits) -> Self {
+        Binder {
+            format,
+            positional,
+            named,
+            positional_iter: positional.iter(),
+            next_index: 0,
+            exhausted: false,
+            limits,
+            missing: MissingBehavior::Error,
+            locale: Locale::C,
+            measure: Measure::Chars,
+            unsupported: None,
+        }
+    }
+
+    fn next_argument(&mut self) -> Option<&'p V> {
+        let value = self.positional_iter.next();
+        match value {
+            Some(_) => self.next_index += 1,
+            None => self.exhausted = true,
+        }
+        value
+    }
+
+    /// Returns the number of positional arguments, if the arguments know it, or if the iterator
+    /// over them has run out.
+    fn positional_len(&self) -> Option<usize> {
+        match self.positional.known_len() {
+            Some(len) => Some(len),
+            None if self.exhausted => Some(self.next_index),
+            None => None,
+        }
+    }
+
+    fn lookup_argument_by_index(&self, idx: usize) -> Option<&'p V> {
+        self.positional.get(idx)
+    }
+
+    fn lookup_argument_by_name(&self, name: &str) -> Option<&'p V> {
+        if !name.contains('.') {
+            return self.named.get(name);
+        }
+        let path: Vec<&str> = name.split('.').collect();
+        self.named.get_path(&path)
+    }
+
+    fn lookup_argument(&mut self, arg: &ArgumentRef) -> Result<&'p V, ParseErrorKind> {
+        match arg {
+            ArgumentRef::Positional(idx) => self.lookup_argument_by_index(*idx),
+            ArgumentRef::FromEnd(distance) => self
+                .positional_len()
+                .and_then(|len| len.checked_sub(*distance))
+                .and_then(|idx| self.lookup_argument_by_index(idx)),
+            ArgumentRef::Next => self.next_argument(),
+            ArgumentRef::Named(name) => self.lookup_argument_by_name(name),
+        }
+        .ok_or_else(|| missing_argument(arg, |limit| count_positional(self, limit)))
+    }
+
+    fn bind_placeholder(
+        &mut self,
+        placeholder: &Placeholder,
+    ) -> Result<Substitution<'p, V>, ParseErrorKind> {
+        let limits = self.limits;
+        let specifier = Specifier {
+            locale: self.locale,
+            measure: self.measure,
+            ..placeholder.specifier.bind(self, &limits)?
+        };
+        let (value, argument) = match self.lookup_argument(&placeholder.argument) {
+            Ok(value) => (value, placeholder.argument.clone()),
+            Err(kind) => match &placeholder.alias {
+                Some(alias) => match self.lookup_argument_by_name(alias) {
+                    Some(value) => (value, ArgumentRef::Named(alias.clone())),
+                    None => return Err(kind),
+                },
+                None => return Err(kind),
+            },
+        };
+        Substitution::with_argument_ref(
+            specifier,
+            value,
+            Some(argument),
+            placeholder.span.clone(),
+            self.unsupported,
+        )
+    }
+
+    fn bind<'s: 'p>(
+        &mut self,
+        segment: &TemplateSegment<'s>,
+    ) -> Result<Segment<'p, V>, ParseError> {
+        match segment {
+            TemplateSegment::Text(text, span) => Ok(self.bind_text(text, span.clone())),
+            TemplateSegment::Placeholder(placeholder) => self.substitute(placeholder),
+        }
+    }
+
+    /// Returns the segment for the given literal text. Passed through text keeps its escaped
+    /// braces, so that it can be parsed again.
+    fn bind_text(&self, text: &'p str, span: Range<usize>) -> Segment<'p, V> {
+        let text = match self.missing {
+            MissingBehavior::Error => text,
+            MissingBehavior::Passthrough => &self.format[span.clone()],
+        };
+        Segment::Text { text, span }
+    }
+
+    /// Binds the placeholder and returns the segment to use in its place. If its argument is
+    /// missing, that's the fallback text of the placeholder, if it has one, or the text of the
+    /// placeholder itself, if that's allowed.
+    fn substitute(&mut self, placeholder: &Placeholder) -> Result<Segment<'p, V>, ParseError> {
+        match self.bind_placeholder(placeholder) {
+            Ok(substitution) => Ok(Segment::Substitution(substitution)),
+            Err(ParseErrorKind::MissingNamedArgument { .. })
+            | Err(ParseErrorKind::ArgumentIndexOutOfRange { .. })
+            | Err(ParseErrorKind::NegativeIndexOutOfRange { .. })
+                if placeholder.fallback.is_some() || self.missing == MissingBehavior::Passthrough =>
+            {
+                let text = placeholder.fallback.as_ref().unwrap_or(&placeholder.span);
+                Ok(Segment::Text {
+                    text: &self.format[text.clone()],
+                    span: placeholder.span.clone(),
+                })
+            }
+            Err(kind) => Err(ParseError::with_span(placeholder.span.clone(), kind)),
+        }
+    }
 }
 
-/// Parses only the format specifier portion of a format argument. For example, in a format
-/// argument specification `{foo:#X}`, this function would parse only the `#X` part.
-pub fn parse_specifier<V, S>(spec_str: &str, value_src: &mut S) -> Result<Specifier, ()>
+impl<'p, V, P, N> ArgumentSource<V> for Binder<'p, V, P, N>
 where
     V: FormatArgument,
-    S: ArgumentSource<V>,
+    P: PositionalArguments<'p, V> + ?Sized,
+    N: NamedArguments<V> + ?Sized,
 {
-    use lazy_static::lazy_static;
-    use regex::Regex;
+    fn next_argument(&mut self) -> Option<&V> {
+        (self as &mut Binder<'p, V, P, N>).next_argument()
+    }
+
+    fn lookup_argument_by_index(&self, idx: usize) -> Option<&V> {
+        (self as &Binder<'p, V, P, N>).lookup_argument_by_index(idx)
+    }
 
-    lazy_static! {
-        static ref SPEC_RE: Regex = Regex::new(concat!(r"(?x) ^", SPEC_REGEX_FRAG!())).unwrap();
+    fn lookup_argument_by_name(&self, name: &str) -> Option<&V> {
+        (self as &Binder<'p, V, P, N>).lookup_argument_by_name(name)
     }
 
-    match SPEC_RE.captures(spec_str) {
-        None => Err(()),
-        Some(captures) => parse_specifier_captures(&captures, value_src)
+    fn positional_len(&self) -> Option<usize> {
+        (self as &Binder<'p, V, P, N>).positional_len()
     }
 }
 
+/// Where a `Parser` takes the segments it binds from.
+enum ParserSource<'p> {
+    /// A formatting string, which is scanned as the parser goes.
+    Format(Scanner<'p>),
+    /// The segments of a `Template`.
+    Template(core::slice::Iter<'p, TemplateSegment<'p>>),
+    /// The formatting string and the segments of an `OwnedTemplate`.
+    OwnedTemplate(&'p str, core::slice::Iter<'p, OwnedTemplateSegment>),
+}
+
 /// An iterator of `Segment`s that correspond to the parts of the formatting string being parsed.
+///
+/// The parser is also the way to combine the options of parsing, which are set with its `with_`
+/// methods. Pass it to `ParsedFormat::from_parser` to collect the result.
 pub struct Parser<'p, V, P, N>
 where
     V: FormatArgument,
     P: PositionalArguments<'p, V> + ?Sized,
-    N: NamedArguments<V>,
+    N: NamedArguments<V> + ?Sized,
 {
-    unparsed: &'p str,
-    parsed_len: usize,
-    positional: &'p P,
-    named: &'p N,
-    positional_iter: P::Iter,
+    source: ParserSource<'p>,
+    binder: Binder<'p, V, P, N>,
+    /// The placeholders bound so far, if the unused arguments are checked at the end.
+    strict: Option<Vec<Placeholder>>,
 }
 
 impl<'p, V, P, N> Parser<'p, V, P, N>
 where
     V: FormatArgument,
     P: PositionalArguments<'p, V> + ?Sized,
-    N: NamedArguments<V>,
+    N: NamedArguments<V> + ?Sized,
 {
     /// Creates a new `Parser` for the given formatting string, positional arguments, and named
     /// arguments.
     pub fn new(format: &'p str, positional: &'p P, named: &'p N) -> Self {
         Parser {
-            unparsed: format,
-            parsed_len: 0,
-            positional,
-            named,
-            positional_iter: positional.iter(),
+            source: ParserSource::Format(Scanner::new(format)),
+            binder: Binder::new(format, positional, named, FormatLimits::default()),
+            strict: None,
         }
     }
 
-    fn advance_and_return<T>(&mut self, advance_by: usize, result: T) -> T {
-        self.unparsed = &self.unparsed[advance_by..];
-        self.parsed_len += advance_by;
-        result
+    /// Makes the parser ignore the whitespace around the argument and around the specifier of
+    /// each substitution, so that `{ foo }` and `{ foo : >8 }` are accepted, unlike in `format!`.
+    /// The whitespace is what `str::trim` removes, and it's ignored right after the opening brace,
+    /// before and after the colon, and right before the closing brace. Whitespace within the
+    /// specifier is not ignored, and neither is whitespace within a dotted name.
+    ///
+    /// Since the whitespace around the specifier is ignored, a space can't be used as the fill
+    /// character at the start of the specifier, nor as the grouping separator at its end.
+    ///
+    /// # Panics
+    /// Panics if the parser binds a template, which is already parsed. Pass the option to
+    /// `Template::parse_with` instead.
+    pub fn with_trimmed_names(mut self) -> Self {
+        self.scanner("with_trimmed_names").trim = true;
+        self
     }
 
-    fn error(&mut self) -> Result<Segment<'p, V>, usize> {
-        self.unparsed = "";
-        Err(self.parsed_len)
+    /// Makes the parser accept a negative index for the argument of a substitution, counting
+    /// from the end of the positional arguments, so that `{-1}` is the last one. This isn't
+    /// supported by `format!`. A negative index beyond the first argument fails with
+    /// `ParseErrorKind::NegativeIndexOutOfRange`, and `{-0}` is an invalid specifier. The width
+    /// and precision can't be taken from an argument with a negative index.
+    ///
+    /// # Panics
+    /// Panics if the parser binds a template, which is already parsed. Pass the option to
+    /// `Template::parse_with` instead.
+    pub fn with_negative_indices(mut self) -> Self {
+        self.scanner("with_negative_indices").negative = true;
+        self
     }
 
-    fn text_segment(&mut self, len: usize) -> Segment<'p, V> {
-        self.advance_and_return(len, Segment::Text(&self.unparsed[..len]))
+    /// Sets the limits on the width and precision that the parser allows. There are no limits by
+    /// default.
+    pub fn with_limits(mut self, limits: FormatLimits) -> Self {
+        self.binder.limits = limits;
+        self
     }
 
-    fn parse_braces(&mut self) -> Result<Segment<'p, V>, usize> {
-        if self.unparsed.len() < 2 {
-            self.error()
-        } else if self.unparsed.as_bytes()[0] == self.unparsed.as_bytes()[1] {
-            Ok(self.advance_and_return(2, Segment::Text(&self.unparsed[..1])))
-        } else {
-            self.parse_substitution()
-        }
+    /// Sets what the parser does with a substitution whose argument is missing, instead of failing.
+    pub fn with_missing_behavior(mut self, missing: MissingBehavior) -> Self {
+        self.binder.missing = missing;
+        self
     }
 
-    fn parse_substitution(&mut self) -> Result<Segment<'p, V>, usize> {
-        use lazy_static::lazy_static;
-        use regex::Regex;
-
-        lazy_static! {
-            static ref ARG_RE: Regex = Regex::new(
-                concat!(
-                    r"(?x)
-                        ^
-                        \{
-                            (?:(?P<index>\d+)|(?P<name>[\p{XID_Start}_][\p{XID_Continue}]*))?
-                            (?:
-                                :
-                    ",
-                    SPEC_REGEX_FRAG!(),
-                    r"
-                            )?
-                    \}"
-                )
-            )
-            .unwrap();
-        }
-
-        match ARG_RE.captures(self.unparsed) {
-            None => self.error(),
-            Some(captures) => match parse_specifier_captures(&captures, self) {
-                Ok(specifier) => self
-                    .lookup_argument(&captures)
-                    .ok_or(())
-                    .and_then(|value| Substitution::new(specifier, value))
-                    .map(|arg| {
-                        self.advance_and_return(
-                            captures.get(0).unwrap().end(),
-                            Segment::Substitution(arg),
-                        )
-                    })
-                    .or_else(|_| self.error()),
-                Err(_) => self.error(),
-            },
-        }
+    /// Sets the locale of every substitution, instead of `Locale::C`.
+    pub fn with_locale(mut self, locale: Locale) -> Self {
+        self.binder.locale = locale;
+        self
     }
 
-    fn next_argument(&mut self) -> Option<&'p V> {
-        self.positional_iter.next()
+    /// Sets how the width and the maximum width of every substitution are measured, instead of
+    /// `Measure::Chars`.
+    pub fn with_measure(mut self, measure: Measure) -> Self {
+        self.binder.measure = measure;
+        self
     }
 
-    fn lookup_argument_by_index(&self, idx: usize) -> Option<&'p V> {
-        self.positional.get(idx)
+    /// Makes the parser accept a value that doesn't support the format of its substitution,
+    /// instead of failing with `ParseErrorKind::UnsupportedFormat`. The value is formatted with
+    /// `unsupported` instead, given the specifier of the substitution, so that a single bad
+    /// argument doesn't fail the whole formatting string.
+    pub fn with_unsupported_formatter(mut self, unsupported: &'p UnsupportedFormatter<V>) -> Self {
+        self.binder.unsupported = Some(unsupported);
+        self
     }
 
-    fn lookup_argument_by_name(&self, name: &str) -> Option<&'p V> {
-        self.named.get(name)
+    /// Makes the parser also fail with `ParseErrorKind::UnusedArguments` if any of the given
+    /// arguments isn't used by the formatting string, like the `format!` macro does. That error
+    /// comes after all the segments, and its offset is the length of the formatting string.
+    ///
+    /// A named argument counts as used if it's referenced by name, or if a dotted name starts with
+    /// it. Only the names listed by `NamedArguments::names` are checked, and its default
+    /// implementation lists none, so a custom map or `FnNamedArguments`, on its own or inside an
+    /// `OrElse`, silently accepts unused names. Likewise, positional arguments are only checked if
+    /// `PositionalArguments::known_len` returns how many there are, which `NextArguments` doesn't.
+    pub fn with_strict_arguments(mut self) -> Self {
+        self.strict = Some(Vec::new());
+        self
     }
 
-    fn lookup_argument(&mut self, captures: &Captures) -> Option<&'p V> {
-        if let Some(idx) = captures.name("index") {
-            idx.as_str()
-                .parse::<usize>()
-                .ok()
-                .and_then(|idx| self.lookup_argument_by_index(idx))
-        } else if let Some(name) = captures.name("name") {
-            self.lookup_argument_by_name(name.as_str())
-        } else {
-            self.next_argument()
+    /// Returns the scanner of a parser for a formatting string, so that the given method can set
+    /// its syntax options.
+    fn scanner(&mut self, method: &str) -> &mut Scanner<'p> {
+        match &mut self.source {
+            ParserSource::Format(scanner) => scanner,
+            _ => panic!("Parser::{} called on a parser for a template", method),
+        }
+    }
+
+    /// Creates a parser that binds the segments of a template, with its limits, behavior for
+    /// missing arguments, locale and measure.
+    fn for_template(
+        source: ParserSource<'p>,
+        format: &'p str,
+        positional: &'p P,
+        named: &'p N,
+        options: (FormatLimits, MissingBehavior, Locale, Measure),
+    ) -> Self {
+        let (limits, missing, locale, measure) = options;
+        let mut binder = Binder::new(format, positional, named, limits);
+        binder.missing = missing;
+        binder.locale = locale;
+        binder.measure = measure;
+        Parser {
+            source,
+            binder,
+            strict: None,
+        }
+    }
+
+    /// Binds the next segment from the source, if any.
+    fn bind_next(&mut self) -> Option<Result<Segment<'p, V>, ParseError>> {
+        let binder = &mut self.binder;
+        let strict = &mut self.strict;
+        match &mut self.source {
+            ParserSource::Format(scanner) => Some(scanner.next()?.and_then(|segment| match segment {
+                TemplateSegment::Text(text, span) => Ok(binder.bind_text(text, span)),
+                TemplateSegment::Placeholder(placeholder) => {
+                    Self::substitute(binder, strict, &placeholder)
+                }
+            })),
+            ParserSource::Template(segments) => Some(match segments.next()? {
+                TemplateSegment::Text(text, span) => Ok(binder.bind_text(text, span.clone())),
+                TemplateSegment::Placeholder(placeholder) => {
+                    Self::substitute(binder, strict, placeholder)
+                }
+            }),
+            ParserSource::OwnedTemplate(format, segments) => Some(match segments.next()? {
+                OwnedTemplateSegment::Text(range, span) => {
+                    Ok(binder.bind_text(&format[range.clone()], span.clone()))
+                }
+                OwnedTemplateSegment::Placeholder(placeholder) => {
+                    Self::substitute(binder, strict, placeholder)
+                }
+            }),
+        }
+    }
+
+    /// Binds the placeholder, and keeps a copy of it if the unused arguments are checked.
+    fn substitute(
+        binder: &mut Binder<'p, V, P, N>,
+        strict: &mut Option<Vec<Placeholder>>,
+        placeholder: &Placeholder,
+    ) -> Result<Segment<'p, V>, ParseError> {
+        if let Some(placeholders) = strict {
+            placeholders.push(placeholder.clone());
         }
+        binder.substitute(placeholder)
     }
 }
 
@@ -357,18 +2626,22 @@ impl<'p, V, P, N> ArgumentSource<V> for Parser<'p, V, P, N>
 where
     V: FormatArgument,
     P: PositionalArguments<'p, V> + ?Sized,
-    N: NamedArguments<V>,
+    N: NamedArguments<V> + ?Sized,
 {
     fn next_argument(&mut self) -> Option<&V> {
-        (self as &mut Parser<'p, V, P, N>).next_argument()
+        self.binder.next_argument()
     }
 
     fn lookup_argument_by_index(&self, idx: usize) -> Option<&V> {
-        (self as &Parser<'p, V, P, N>).lookup_argument_by_index(idx)
+        self.binder.lookup_argument_by_index(idx)
     }
 
     fn lookup_argument_by_name(&self, name: &str) -> Option<&V> {
-        (self as &Parser<'p, V, P, N>).lookup_argument_by_name(name)
+        self.binder.lookup_argument_by_name(name)
+    }
+
+    fn positional_len(&self) -> Option<usize> {
+        self.binder.positional_len()
     }
 }
 
@@ -376,21 +2649,28 @@ impl<'p, V, P, N> Iterator for Parser<'p, V, P, N>
 where
     V: FormatArgument,
     P: PositionalArguments<'p, V> + ?Sized,
-    N: NamedArguments<V>,
+    N: NamedArguments<V> + ?Sized,
 {
-    type Item = Result<Segment<'p, V>, usize>;
+    type Item = Result<Segment<'p, V>, ParseError>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        static BRACES: &[char] = &['{', '}'];
-
-        if self.unparsed.len() == 0 {
-            return None;
-        }
-
-        match self.unparsed.find(BRACES) {
-            None => Some(Ok(self.text_segment(self.unparsed.len()))),
-            Some(0) => Some(self.parse_braces()),
-            Some(brace_idx) => Some(Ok(self.text_segment(brace_idx))),
+        let result = match self.bind_next() {
+            Some(result) => result,
+            None => {
+                let placeholders = self.strict.take()?;
+                let binder = &self.binder;
+                let kind = unused_arguments(&placeholders, binder.positional, binder.named)?;
+                Err(ParseError::new(binder.format.len(), kind))
+            }
+        };
+        if result.is_err() {
+            self.strict = None;
+            match &mut self.source {
+                ParserSource::Format(scanner) => scanner.stop(),
+                ParserSource::Template(segments) => *segments = <[_]>::iter(&[]),
+                ParserSource::OwnedTemplate(_, segments) => *segments = <[_]>::iter(&[]),
+            }
         }
+        Some(result)
     }
 }
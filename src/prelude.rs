@@ -0,0 +1,196 @@
+//! Re-exports the types that most uses of the crate need, along with `Primitive`, a ready-made
+//! argument type for formatting a mix of numbers and strings.
+//!
+//! # Examples
+//! ```
+//! use rt_format::prelude::*;
+//!
+//! let args: Vec<Primitive> = vec![42.into(), 2.5.into(), "apples".into()];
+//! let parsed = ParsedFormat::parse("{:#x} {:>5.1} {}", &args, &NoNamedArguments).unwrap();
+//! assert_eq!("0x2a   2.5 apples", parsed.to_string());
+//! ```
+
+use core::convert::TryFrom;
+use core::fmt;
+
+pub use crate::argument::{FormatArgument, NoNamedArguments, NoPositionalArguments};
+pub use crate::parser::{ParsedFormat, Parser, Template};
+pub use crate::{Format, Specifier};
+
+use crate::Align;
+
+/// The formatting traits that every integer type implements.
+trait Integer:
+    fmt::Display
+    + fmt::Debug
+    + fmt::Octal
+    + fmt::LowerHex
+    + fmt::UpperHex
+    + fmt::Binary
+    + fmt::LowerExp
+    + fmt::UpperExp
+{
+}
+
+impl<T> Integer for T where
+    T: fmt::Display
+        + fmt::Debug
+        + fmt::Octal
+        + fmt::LowerHex
+        + fmt::UpperHex
+        + fmt::Binary
+        + fmt::LowerExp
+        + fmt::UpperExp
+{
+}
+
+/// The formatting traits that every numeric type implements.
+trait Number: fmt::Display + fmt::Debug + fmt::LowerExp + fmt::UpperExp {}
+
+impl<T: fmt::Display + fmt::Debug + fmt::LowerExp + fmt::UpperExp> Number for T {}
+
+/// The formatting traits that every primitive type implements.
+trait Value: fmt::Display + fmt::Debug {}
+
+impl<T: fmt::Display + fmt::Debug> Value for T {}
+
+/// Generates the `Primitive` enum, with a variant for each of the given types, the `From` impls,
+/// and the methods that view the wrapped value through the traits it implements.
+macro_rules! primitives {
+    (
+        integers: $($int:ident($int_type:ty)),+;
+        floats: $($float:ident($float_type:ty)),+;
+        others: $($other:ident($other_type:ty)),+;
+    ) => {
+        /// A value of one of the primitive types, or a string slice.
+        ///
+        /// Integers support all the formats except the pointer format, floats support the display,
+        /// debug and exponential formats, and the rest support the display and debug formats.
+        /// Non-negative integers can supply the width or precision. Like in `format!`, numbers are
+        /// aligned to the right by default, and everything else to the left.
+        #[derive(Debug, Copy, Clone, PartialEq)]
+        #[allow(missing_docs)]
+        pub enum Primitive<'a> {
+            $($int($int_type),)+
+            $($float($float_type),)+
+            $($other($other_type),)+
+        }
+
+        $(
+            impl<'a> From<$int_type> for Primitive<'a> {
+                fn from(value: $int_type) -> Self {
+                    Primitive::$int(value)
+                }
+            }
+        )+
+        $(
+            impl<'a> From<$float_type> for Primitive<'a> {
+                fn from(value: $float_type) -> Self {
+                    Primitive::$float(value)
+                }
+            }
+        )+
+        $(
+            impl<'a> From<$other_type> for Primitive<'a> {
+                fn from(value: $other_type) -> Self {
+                    Primitive::$other(value)
+                }
+            }
+        )+
+
+        impl<'a> Primitive<'a> {
+            fn integer(&self) -> Option<&dyn Integer> {
+                match self {
+                    $(Primitive::$int(val) => Some(val),)+
+                    _ => None,
+                }
+            }
+
+            fn number(&self) -> Option<&dyn Number> {
+                match self {
+                    $(Primitive::$int(val) => Some(val),)+
+                    $(Primitive::$float(val) => Some(val),)+
+                    _ => None,
+                }
+            }
+
+            fn value(&self) -> &dyn Value {
+                match self {
+                    $(Primitive::$int(val) => val,)+
+                    $(Primitive::$float(val) => val,)+
+                    $(Primitive::$other(val) => val,)+
+                }
+            }
+
+            fn size(&self) -> Option<usize> {
+                match self {
+                    $(Primitive::$int(val) => usize::try_from(*val).ok(),)+
+                    _ => None,
+                }
+            }
+        }
+    };
+}
+
+primitives! {
+    integers: I8(i8), I16(i16), I32(i32), I64(i64), I128(i128), Isize(isize),
+        U8(u8), U16(u16), U32(u32), U64(u64), U128(u128), Usize(usize);
+    floats: F32(f32), F64(f64);
+    others: Bool(bool), Char(char), Str(&'a str);
+}
+
+impl<'a> FormatArgument for Primitive<'a> {
+    fn supports_format(&self, specifier: &Specifier) -> bool {
+        match specifier.format {
+            Format::Display | Format::Debug => true,
+            Format::LowerExp | Format::UpperExp => self.number().is_some(),
+            Format::Octal | Format::LowerHex | Format::UpperHex | Format::Binary => {
+                self.integer().is_some()
+            }
+            Format::Pointer | Format::Custom(_) => false,
+        }
+    }
+
+    fn fmt_display(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(self.value(), f)
+    }
+
+    fn fmt_debug(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(self.value(), f)
+    }
+
+    fn fmt_octal(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.integer().ok_or(fmt::Error).and_then(|val| fmt::Octal::fmt(val, f))
+    }
+
+    fn fmt_lower_hex(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.integer().ok_or(fmt::Error).and_then(|val| fmt::LowerHex::fmt(val, f))
+    }
+
+    fn fmt_upper_hex(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.integer().ok_or(fmt::Error).and_then(|val| fmt::UpperHex::fmt(val, f))
+    }
+
+    fn fmt_binary(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.integer().ok_or(fmt::Error).and_then(|val| fmt::Binary::fmt(val, f))
+    }
+
+    fn fmt_lower_exp(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.number().ok_or(fmt::Error).and_then(|val| fmt::LowerExp::fmt(val, f))
+    }
+
+    fn fmt_upper_exp(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.number().ok_or(fmt::Error).and_then(|val| fmt::UpperExp::fmt(val, f))
+    }
+
+    fn natural_alignment(&self) -> Align {
+        match self.number() {
+            Some(_) => Align::Right,
+            None => Align::Left,
+        }
+    }
+
+    fn to_usize(&self) -> Result<usize, ()> {
+        self.size().ok_or(())
+    }
+}
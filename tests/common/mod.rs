@@ -4,23 +4,25 @@ use std::cmp::PartialEq;
 use std::convert::TryInto;
 use std::fmt;
 
-use rt_format::{Format, FormatArgument, Specifier};
+use rt_format::{Align, Format, FormatArgument, Specifier};
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
 #[allow(dead_code)]
 pub enum Variant {
     Int(i32),
     Float(f64),
+    Str(String),
 }
 
 impl FormatArgument for Variant {
     fn supports_format(&self, spec: &Specifier) -> bool {
         match self {
-            Self::Int(_) => true,
-            Self::Float(_) => match spec.format {
-                Format::Display | Format::Debug | Format::LowerExp | Format::UpperExp => true,
-                _ => false,
-            },
+            Self::Int(_) => !matches!(spec.format, Format::Pointer | Format::Custom(_)),
+            Self::Float(_) => matches!(
+                spec.format,
+                Format::Display | Format::Debug | Format::LowerExp | Format::UpperExp
+            ),
+            Self::Str(_) => matches!(spec.format, Format::Display | Format::Debug),
         }
     }
 
@@ -28,6 +30,7 @@ impl FormatArgument for Variant {
         match self {
             Self::Int(val) => fmt::Display::fmt(&val, f),
             Self::Float(val) => fmt::Display::fmt(&val, f),
+            Self::Str(val) => fmt::Display::fmt(&val, f),
         }
     }
 
@@ -67,6 +70,7 @@ impl FormatArgument for Variant {
         match self {
             Self::Int(val) => fmt::LowerExp::fmt(&val, f),
             Self::Float(val) => fmt::LowerExp::fmt(&val, f),
+            _ => Err(fmt::Error),
         }
     }
 
@@ -74,13 +78,21 @@ impl FormatArgument for Variant {
         match self {
             Self::Int(val) => fmt::UpperExp::fmt(&val, f),
             Self::Float(val) => fmt::UpperExp::fmt(&val, f),
+            _ => Err(fmt::Error),
+        }
+    }
+
+    fn natural_alignment(&self) -> Align {
+        match self {
+            Self::Str(_) => Align::Left,
+            _ => Align::Right,
         }
     }
 
     fn to_usize(&self) -> Result<usize, ()> {
         match self {
             Variant::Int(val) => (*val).try_into().map_err(|_| ()),
-            Variant::Float(_) => Err(()),
+            _ => Err(()),
         }
     }
 }
@@ -0,0 +1,47 @@
+#![cfg(feature = "serde_json")]
+
+use rt_format::argument::JsonValue;
+use rt_format::ParsedFormat;
+use serde_json::json;
+
+fn fmt_json(format: &str, named: &[(&str, JsonValue)]) -> Result<String, rt_format::ParseError> {
+    ParsedFormat::parse(format, &[] as &[JsonValue], named).map(|parsed| parsed.to_string())
+}
+
+#[test]
+fn json_values() {
+    let data = json!({
+        "name": "disk",
+        "used": 97,
+        "delta": -3,
+        "ratio": 0.5,
+        "ok": false,
+        "owner": null,
+        "tags": ["a", "b"],
+        "limits": { "max": 100 },
+    });
+    let named: Vec<(&str, JsonValue)> = data
+        .as_object()
+        .unwrap()
+        .iter()
+        .map(|(key, value)| (key.as_str(), JsonValue(value)))
+        .collect();
+
+    assert_eq!(
+        Ok("disk \"disk\" 97 0x61 -3 0.500 5e-1 false null".to_string()),
+        fmt_json(
+            "{name} {name:?} {used} {used:#x} {delta} {ratio:.3} {ratio:e} {ok} {owner}",
+            &named
+        )
+    );
+    assert_eq!(Ok("[\"a\",\"b\"]".to_string()), fmt_json("{tags:?}", &named));
+    assert_eq!(Ok("{\n  \"max\": 100\n}".to_string()), fmt_json("{limits:#?}", &named));
+    assert_eq!(Ok(format!("{:97}|", "disk")), fmt_json("{name:used$}|", &named));
+    assert_eq!(Ok("  97".to_string()), fmt_json("{used:>4}", &named));
+
+    assert!(fmt_json("{ratio:x}", &named).is_err());
+    assert!(fmt_json("{name:x}", &named).is_err());
+    assert!(fmt_json("{ok:?}", &named).is_err());
+    assert!(fmt_json("{tags}", &named).is_err());
+    assert!(fmt_json("{name:ratio$}", &named).is_err());
+}
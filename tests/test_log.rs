@@ -0,0 +1,47 @@
+#![cfg(feature = "log")]
+
+use std::sync::Mutex;
+
+use log::{Level, LevelFilter, Log, Metadata, Record};
+use rt_format::argument::NoPositionalArguments;
+use rt_format::{rt_log, ParseErrorKind};
+
+mod common;
+use common::Variant;
+
+struct Capture(Mutex<Vec<(Level, String)>>);
+
+impl Log for Capture {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= Level::Info
+    }
+
+    fn log(&self, record: &Record) {
+        if self.enabled(record.metadata()) {
+            let message = (record.level(), record.args().to_string());
+            self.0.lock().unwrap().push(message);
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+static LOGGER: Capture = Capture(Mutex::new(Vec::new()));
+
+#[test]
+fn rt_log() {
+    log::set_logger(&LOGGER).unwrap();
+    log::set_max_level(LevelFilter::Info);
+
+    let named = [("name", Variant::Str("disk".to_string()))];
+    rt_log!(Level::Warn, "{name} is {:>3}% full", &[Variant::Int(97)], &named).unwrap();
+    rt_log!(Level::Debug, "{name} is fine", &NoPositionalArguments, &named).unwrap();
+    rt_log!(Level::Debug, "{", &NoPositionalArguments, &named).unwrap();
+    let err = rt_log!(Level::Error, "{size}", &NoPositionalArguments, &named).unwrap_err();
+    assert_eq!(&ParseErrorKind::MissingNamedArgument { name: "size".to_string() }, err.kind());
+
+    assert_eq!(
+        vec![(Level::Warn, "disk is  97% full".to_string())],
+        *LOGGER.0.lock().unwrap()
+    );
+}
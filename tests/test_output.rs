@@ -1,6 +1,10 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
 
-use rt_format::argument::NoNamedArguments;
+use rt_format::argument::{
+    BorrowedArguments, FormatArgument, NamedArguments, NoNamedArguments, NoPositionalArguments
+};
+use rt_format::parser::Parser;
 use rt_format::ParsedFormat;
 
 mod common;
@@ -11,7 +15,7 @@ fn fmt_args(spec: &str, args: &[Variant]) -> String {
 }
 
 fn fmt_args_map(spec: &str, positional: &[Variant], named: &[(&str, Variant)]) -> String {
-    let mut map = HashMap::new();
+    let mut map = BTreeMap::new();
     for (key, value) in named {
         map.insert(*key, value);
     }
@@ -33,6 +37,26 @@ fn align_right() {
     assert_eq!("#    42#", fmt_args("#{:>6}#", &[Variant::Int(42)]));
 }
 
+#[test]
+fn fill_left() {
+    assert_eq!("#42****#", fmt_args("#{:*<6}#", &[Variant::Int(42)]));
+}
+
+#[test]
+fn fill_center() {
+    assert_eq!("#-42--#", fmt_args("#{:-^5}#", &[Variant::Int(42)]));
+}
+
+#[test]
+fn fill_right() {
+    assert_eq!("#****42#", fmt_args("#{:*>6}#", &[Variant::Int(42)]));
+}
+
+#[test]
+fn fill_multibyte() {
+    assert_eq!("#ññ+42.0ññ#", fmt_args("#{:ñ^+9.1}#", &[Variant::Float(42.0)]));
+}
+
 #[test]
 fn sign_always() {
     assert_eq!("+42", fmt_args("{:+}", &[Variant::Int(42)]));
@@ -93,6 +117,37 @@ fn precision_embedded() {
     assert_eq!("#42.04200#", fmt_args("#{:.5}#", &[Variant::Float(42.042)]));
 }
 
+#[test]
+fn precision_truncates_string() {
+    assert_eq!(
+        format!("#{:.3}#", "hello"),
+        fmt_args("#{:.3}#", &[Variant::Str("hello".to_string())])
+    );
+    assert_eq!(
+        format!("#{:.10}#", "hello"),
+        fmt_args("#{:.10}#", &[Variant::Str("hello".to_string())])
+    );
+}
+
+#[test]
+fn precision_truncates_string_with_width() {
+    assert_eq!(
+        format!("#{:>6.3}#{:*^7.2}#", "hello", "ñandú"),
+        fmt_args(
+            "#{:>6.3}#{:*^7.2}#",
+            &[Variant::Str("hello".to_string()), Variant::Str("ñandú".to_string())]
+        )
+    );
+}
+
+#[test]
+fn precision_truncates_string_by_argument() {
+    assert_eq!(
+        format!("#{:.*}#", 2, "hello"),
+        fmt_args("#{:.*}#", &[Variant::Int(2), Variant::Str("hello".to_string())])
+    );
+}
+
 #[test]
 fn precision_by_index() {
     assert_eq!(
@@ -113,6 +168,23 @@ fn precision_by_name() {
     );
 }
 
+#[test]
+fn width_and_precision_by_name() {
+    assert_eq!(
+        format!("#{:8.2}#{:9.3}#", 42.042, -1.5),
+        fmt_args_map(
+            "#{:w$.p$}#{:min_width$._prec$}#",
+            &[Variant::Float(42.042), Variant::Float(-1.5)],
+            &[
+                ("w", Variant::Int(8)),
+                ("p", Variant::Int(2)),
+                ("min_width", Variant::Int(9)),
+                ("_prec", Variant::Int(3)),
+            ]
+        )
+    );
+}
+
 #[test]
 fn precision_by_asterisk() {
     assert_eq!(
@@ -172,3 +244,1193 @@ fn smoke_test() {
         )
     );
 }
+
+#[test]
+fn write_to_existing_buffer() {
+    let args = [Variant::Int(42)];
+    let parsed = ParsedFormat::parse("[{:>4}]", &args, &NoNamedArguments).unwrap();
+    let mut buf = String::from("value: ");
+    parsed.write_to(&mut buf).unwrap();
+    assert_eq!("value: [  42]", buf);
+}
+
+#[test]
+fn write_to_failing_writer() {
+    struct FailingWriter;
+    impl fmt::Write for FailingWriter {
+        fn write_str(&mut self, _: &str) -> fmt::Result {
+            Err(fmt::Error)
+        }
+    }
+
+    let args = [Variant::Int(42)];
+    let parsed = ParsedFormat::parse("{}", &args, &NoNamedArguments).unwrap();
+    assert_eq!(Err(fmt::Error), parsed.write_to(&mut FailingWriter));
+}
+
+#[test]
+fn std_value() {
+    use rt_format::StdValue;
+
+    let args = [StdValue(42u64), StdValue(9)];
+    let parsed = ParsedFormat::parse("[{:#08x}] [{0:>1$b}]", &args, &NoNamedArguments).unwrap();
+    assert_eq!("[0x00002a] [   101010]", format!("{}", parsed));
+
+    let args = [StdValue(7i64), StdValue(-2)];
+    assert!(ParsedFormat::parse("{:1$}", &args, &NoNamedArguments).is_err());
+}
+
+#[test]
+fn combined_arguments() {
+    use rt_format::argument::CombinedArguments;
+
+    let args: CombinedArguments<Variant> = vec![
+        (Some("foo".to_string()), Variant::Int(-1)),
+        (None, Variant::Int(42)),
+        (Some("bar".to_string()), Variant::Float(42.042)),
+        (None, Variant::Int(17)),
+        (Some("foo".to_string()), Variant::Int(-2)),
+    ]
+    .into_iter()
+    .collect();
+    assert_eq!(
+        "42 17 42 -1 42.042",
+        format!("{}", ParsedFormat::parse("{} {} {0} {foo} {bar}", &args, &args).unwrap())
+    );
+}
+
+#[test]
+fn named_btree_map() {
+    use std::collections::BTreeMap;
+
+    let mut map = BTreeMap::new();
+    map.insert("foo".to_string(), Variant::Int(42));
+    let parsed = ParsedFormat::parse("{foo:#x}", &NoPositionalArguments, &map).unwrap();
+    assert_eq!("0x2a", format!("{}", parsed));
+
+    let value = Variant::Float(42.042);
+    let mut map = BTreeMap::new();
+    map.insert("foo", &value);
+    let parsed: ParsedFormat<Variant> =
+        ParsedFormat::parse("{foo:.1}", &NoPositionalArguments, &map).unwrap();
+    assert_eq!("42.0", format!("{}", parsed));
+}
+
+#[test]
+fn named_tuple_slice() {
+    let named = [("foo", Variant::Int(42)), ("bar", Variant::Int(17)), ("foo", Variant::Int(-1))];
+    let parsed = ParsedFormat::parse("{bar} {foo}", &NoPositionalArguments, &named).unwrap();
+    assert_eq!("17 42", format!("{}", parsed));
+
+    let parsed = ParsedFormat::parse("{bar} {foo}", &NoPositionalArguments, &named[1..]).unwrap();
+    assert_eq!("17 -1", format!("{}", parsed));
+}
+
+#[test]
+fn format_pointer() {
+    use rt_format::{Format, FormatArgument, Specifier};
+
+    struct Ptr(*const u8);
+    impl FormatArgument for Ptr {
+        fn supports_format(&self, spec: &Specifier) -> bool {
+            spec.format == Format::Pointer
+        }
+        fn fmt_pointer(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            fmt::Pointer::fmt(&self.0, f)
+        }
+    }
+
+    let byte = 42u8;
+    let args = [Ptr(&byte)];
+    let parsed = ParsedFormat::parse("{:p} {0:>20p}", &args, &NoNamedArguments).unwrap();
+    assert_eq!(format!("{:p} {0:>20p}", &byte), format!("{}", parsed));
+    assert!(ParsedFormat::parse("{:p}", &[Variant::Int(42)], &NoNamedArguments).is_err());
+}
+
+#[test]
+fn default_format_methods() {
+    use rt_format::FormatArgument;
+
+    struct Name(&'static str);
+    impl FormatArgument for Name {
+        fn fmt_display(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            f.pad(self.0)
+        }
+    }
+
+    let args = [Name("foo")];
+    let parsed = ParsedFormat::parse("[{:^7}]", &args, &NoNamedArguments).unwrap();
+    assert_eq!("[  foo  ]", format!("{}", parsed));
+    assert!(ParsedFormat::parse("{:?}", &args, &NoNamedArguments).is_err());
+    assert!(ParsedFormat::parse("{:1$}", &[Name("foo"), Name("5")], &NoNamedArguments).is_err());
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn write_io() {
+    let args = [Variant::Int(42)];
+    let parsed = ParsedFormat::parse("[{:>4}]", &args, &NoNamedArguments).unwrap();
+    let mut buf = Vec::new();
+    parsed.write_io(&mut buf).unwrap();
+    assert_eq!(b"[  42]", &buf[..]);
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn write_io_error() {
+    use std::io;
+
+    struct FailingWriter;
+    impl io::Write for FailingWriter {
+        fn write(&mut self, _: &[u8]) -> io::Result<usize> {
+            Err(io::Error::new(io::ErrorKind::BrokenPipe, "broken"))
+        }
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    let args = [Variant::Int(42)];
+    let parsed = ParsedFormat::parse("{}", &args, &NoNamedArguments).unwrap();
+    assert_eq!(io::ErrorKind::BrokenPipe, parsed.write_io(&mut FailingWriter).unwrap_err().kind());
+}
+
+#[test]
+fn try_format() {
+    use rt_format::{try_format, try_format_named, try_format_positional};
+
+    let named = [("foo", Variant::Int(42))];
+    assert_eq!(
+        Ok("17 0x2a".to_string()),
+        try_format("{} {foo:#x}", &[Variant::Int(17)], &named)
+    );
+    assert_eq!(Ok("+17".to_string()), try_format_positional("{:+}", &[Variant::Int(17)]));
+    assert_eq!(Ok("  42".to_string()), try_format_named("{foo:4}", &named));
+    assert_eq!(4, try_format_named("foo {bar}", &named).unwrap_err().offset());
+}
+
+#[test]
+fn parse_positional_and_named() {
+    let parsed = ParsedFormat::parse_positional("{} {0:+}", &[Variant::Int(17)]).unwrap();
+    assert_eq!("17 +17", parsed.to_string());
+
+    let named = [("foo", Variant::Int(42))];
+    let parsed = ParsedFormat::parse_named("{foo:4}", &named).unwrap();
+    assert_eq!("  42", parsed.to_string());
+    assert_eq!(0, ParsedFormat::parse_named("{}", &named).unwrap_err().offset());
+    let err = ParsedFormat::parse_positional("foo {bar}", &[Variant::Int(1)]).unwrap_err();
+    assert_eq!(4, err.offset());
+}
+
+#[test]
+fn width_from_first_argument() {
+    assert_eq!("#    5#", fmt_args("#{:0$}#", &[Variant::Int(5)]));
+    assert_eq!("#00005#", fmt_args("#{:00$}#", &[Variant::Int(5)]));
+}
+
+#[test]
+fn named_path() {
+    struct Order {
+        id: Variant,
+        customer: HashMap<String, Variant>,
+    }
+
+    impl NamedArguments<Variant> for Order {
+        fn get(&self, key: &str) -> Option<&Variant> {
+            match key {
+                "id" => Some(&self.id),
+                _ => None,
+            }
+        }
+
+        fn get_path(&self, path: &[&str]) -> Option<&Variant> {
+            match path {
+                ["customer", key] => self.customer.get(*key),
+                [key] => self.get(key),
+                _ => None,
+            }
+        }
+    }
+
+    let mut customer = HashMap::new();
+    customer.insert("email".to_string(), Variant::Str("foo@example.com".to_string()));
+    let order = Order { id: Variant::Int(42), customer };
+
+    assert_eq!(
+        "#42 foo@example.com#",
+        format!(
+            "{}",
+            ParsedFormat::parse("#{id} {customer.email}#", &NoPositionalArguments, &order).unwrap()
+        )
+    );
+}
+
+#[test]
+fn trait_object_arguments() {
+    use rt_format::StdValue;
+
+    let boxed: Vec<Box<dyn FormatArgument>> = vec![
+        Box::new(Variant::Int(42)),
+        Box::new(StdValue(6)),
+        Box::new(Variant::Str("foo".to_string())),
+    ];
+    assert_eq!(
+        "#    42|foo#",
+        format!("{}", ParsedFormat::parse("#{:1$}|{2}#", &boxed, &NoNamedArguments).unwrap())
+    );
+
+    let int = Variant::Int(42);
+    let value = StdValue(1500);
+    let borrowed: [&dyn FormatArgument; 2] = [&int, &value];
+    assert_eq!(
+        "#2a 1.5e3#",
+        format!("{}", ParsedFormat::parse("#{:x} {:e}#", &borrowed, &NoNamedArguments).unwrap())
+    );
+}
+
+#[test]
+fn size_arguments_and_cursor() {
+    assert_eq!(
+        format!("{} {0:.*}", 1.5, 2),
+        fmt_args("{} {0:.*}", &[Variant::Float(1.5), Variant::Int(2)])
+    );
+    assert_eq!(
+        format!("{:1$} {}", 7, 5),
+        fmt_args("{:1$} {}", &[Variant::Int(7), Variant::Int(5)])
+    );
+    assert_eq!(
+        format!("{:.*} {}", 2, 1.0, "x"),
+        fmt_args(
+            "{:.*} {}",
+            &[Variant::Int(2), Variant::Float(1.0), Variant::Str("x".to_string())]
+        )
+    );
+    assert_eq!(
+        format!("{:1$.*} {}", 3, 8, 1.0),
+        fmt_args("{:1$.*} {}", &[Variant::Int(3), Variant::Int(8), Variant::Float(1.0)])
+    );
+    assert_eq!(
+        format!("{:w$} {}", 7, 8, w = 5),
+        fmt_args_map("{:w$} {}", &[Variant::Int(7), Variant::Int(8)], &[("w", Variant::Int(5))])
+    );
+}
+
+#[test]
+fn format_one() {
+    use rt_format::{format_one, Align, Format, Specifier};
+
+    let specifier = Specifier::builder().fill('-').align(Align::Center).width(6).build();
+    assert_eq!(Ok("--42--".to_string()), format_one(&specifier, &Variant::Int(42)));
+
+    let specifier = Specifier::builder().format(Format::LowerHex).build();
+    assert_eq!(Ok("2a".to_string()), format_one(&specifier, &Variant::Int(42)));
+    assert_eq!(Err(()), format_one(&specifier, &Variant::Str("foo".to_string())));
+}
+
+#[test]
+fn format_streaming() {
+    use rt_format::{format_streaming, FormatError, ParseError, ParseErrorKind};
+
+    let args = [Variant::Int(42), Variant::Str("foo".to_string())];
+
+    let mut out = String::new();
+    assert_eq!(Ok(()), format_streaming("#{:x} {}#", &args, &NoNamedArguments, &mut out));
+    assert_eq!("#2a foo#", out);
+
+    let mut out = String::new();
+    assert_eq!(
+        Err(FormatError::Parse(ParseError::with_span(
+            7..9,
+            ParseErrorKind::ArgumentIndexOutOfRange { index: 2, available: 2 }
+        ))),
+        format_streaming("#{} {} {}#", &args, &NoNamedArguments, &mut out)
+    );
+    assert_eq!("#42 foo ", out);
+}
+
+#[test]
+fn grouping() {
+    assert_eq!("1,234,567", fmt_args("{:,}", &[Variant::Int(1234567)]));
+    assert_eq!("123", fmt_args("{:,}", &[Variant::Int(123)]));
+    assert_eq!("-1_234", fmt_args("{:_}", &[Variant::Int(-1234)]));
+    assert_eq!("+1 234", fmt_args("{:+ }", &[Variant::Int(1234)]));
+    assert_eq!("1234_5678", fmt_args("{:_x}", &[Variant::Int(0x12345678)]));
+    assert_eq!("0b1111_1111", fmt_args("{:#_b}", &[Variant::Int(255)]));
+    assert_eq!("1,234,567.89", fmt_args("{:,.2}", &[Variant::Float(1234567.891)]));
+    assert_eq!("Int(1234)", fmt_args("{:,?}", &[Variant::Int(1234)]));
+}
+
+#[test]
+fn grouping_numbers_only() {
+    use rt_format::StdValue;
+
+    let date = [Variant::Str("2024-01-01".to_string())];
+    assert_eq!("2024-01-01", fmt_args("{:,}", &date));
+    assert_eq!("2024-01-01  ", fmt_args("{:12_}", &date));
+    assert_eq!("12345", fmt_args("{:,}", &[Variant::Str("12345".to_string())]));
+
+    let args = [StdValue(1234)];
+    let parsed = ParsedFormat::parse("{:,}", &args, &NoNamedArguments).unwrap();
+    assert_eq!("1,234", parsed.to_string());
+}
+
+#[test]
+fn grouping_with_width() {
+    assert_eq!(" 1,234,567", fmt_args("{:10,}", &[Variant::Int(1234567)]));
+    assert_eq!("1,234,567***", fmt_args("{:*<12,}", &[Variant::Int(1234567)]));
+    assert_eq!("-001,234,567", fmt_args("{:012,}", &[Variant::Int(-1234567)]));
+    assert_eq!("0x00001_0000", fmt_args("{:#012_x}", &[Variant::Int(0x10000)]));
+}
+
+#[test]
+fn alternate_debug() {
+    use rt_format::{Format, Specifier};
+
+    #[derive(Debug)]
+    #[allow(dead_code)]
+    struct Point {
+        x: i32,
+        y: i32,
+    }
+
+    struct Debuggable(Point);
+
+    impl FormatArgument for Debuggable {
+        fn supports_format(&self, specifier: &Specifier) -> bool {
+            specifier.format == Format::Debug
+        }
+
+        fn fmt_debug(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            fmt::Debug::fmt(&self.0, f)
+        }
+    }
+
+    let args = [Debuggable(Point { x: 1, y: 2 })];
+    let format = |spec| ParsedFormat::parse(spec, &args, &NoNamedArguments).unwrap().to_string();
+    assert_eq!(format!("{:?}", args[0].0), format("{:?}"));
+    assert_eq!(format!("{:#?}", args[0].0), format("{:#?}"));
+    assert_eq!("Point {\n    x: 1,\n    y: 2,\n}", format("{:#?}"));
+}
+
+#[test]
+fn alternate_flag_forwarded() {
+    use rt_format::{Format, Specifier};
+
+    struct Flagged;
+
+    impl FormatArgument for Flagged {
+        fn supports_format(&self, specifier: &Specifier) -> bool {
+            matches!(specifier.format, Format::Display | Format::Debug)
+        }
+
+        fn fmt_display(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            f.pad(if f.alternate() { "alt display" } else { "display" })
+        }
+
+        fn fmt_debug(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            f.pad(if f.alternate() { "alt debug" } else { "debug" })
+        }
+    }
+
+    let args = [Flagged];
+    let format = |spec| ParsedFormat::parse(spec, &args, &NoNamedArguments).unwrap().to_string();
+    assert_eq!("debug", format("{:?}"));
+    assert_eq!("alt debug", format("{:#?}"));
+    assert_eq!("[alt debug  ]", format("[{:<#11?}]"));
+    assert_eq!("display alt display", format("{} {0:#}"));
+}
+
+#[test]
+fn borrowed_arguments() {
+    let first = Variant::Int(42);
+    let second = Variant::Str("foo".to_string());
+    let args = vec![&second, &first];
+    assert_eq!(
+        "#foo 2a foo#",
+        format!(
+            "{}",
+            ParsedFormat::parse("#{} {:x} {0}#", &BorrowedArguments(&args), &NoNamedArguments)
+                .unwrap()
+        )
+    );
+}
+
+#[test]
+fn max_width() {
+    let long = || Variant::Str("Hello, world!".to_string());
+    assert_eq!("#Hello#", fmt_args("#{:~5}#", &[long()]));
+    assert_eq!("#Hell…#", fmt_args("#{:…5}#", &[long()]));
+    assert_eq!("#Hello, world!#", fmt_args("#{:~20}#", &[long()]));
+    assert_eq!("#1234.5#", fmt_args("#{:~6}#", &[Variant::Float(1234.56789)]));
+    assert_eq!("#1,23…#", fmt_args("#{:,…5}#", &[Variant::Int(1234567)]));
+    assert_eq!("#ünïc#", fmt_args("#{:~4}#", &[Variant::Str("ünïcödé".to_string())]));
+    assert_eq!("##", fmt_args("#{:…0}#", &[long()]));
+}
+
+#[test]
+fn max_width_with_width() {
+    let long = || Variant::Str("Hello, world!".to_string());
+    assert_eq!("#Hello   #", fmt_args("#{:8~5}#", &[long()]));
+    assert_eq!("#-Hell…--#", fmt_args("#{:-^8…5}#", &[long()]));
+    assert_eq!("#      42#", fmt_args("#{:8~5}#", &[Variant::Int(42)]));
+    assert_eq!("#foo     #", fmt_args("#{:8~5}#", &[Variant::Str("foo".to_string())]));
+}
+
+#[test]
+fn escaped_braces_next_to_substitutions() {
+    let args = [Variant::Int(42)];
+    assert_eq!(format!("}}{}{{", 42), fmt_args("}}{}{{", &args));
+    assert_eq!(format!("{}{{", 42), fmt_args("{}{{", &args));
+    assert_eq!(format!("a}}b"), fmt_args("a}}b", &args[..0]));
+    assert_eq!(format!("{{{}}}", 42), fmt_args("{{{}}}", &args));
+    assert_eq!(format!("{{}}{}}}{{", 42), fmt_args("{{}}{}}}{{", &args));
+}
+
+#[test]
+fn custom_format() {
+    use rt_format::{Format, Specifier};
+
+    struct Celsius(f64);
+    impl FormatArgument for Celsius {
+        fn supports_format(&self, spec: &Specifier) -> bool {
+            matches!(spec.format, Format::Display | Format::Custom('f'))
+        }
+        fn fmt_display(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            write!(f, "{}°C", self.0)
+        }
+        fn fmt_custom(&self, tag: char, f: &mut fmt::Formatter) -> fmt::Result {
+            match tag {
+                'f' => f.pad(&format!("{}°F", self.0 * 1.8 + 32.0)),
+                _ => Err(fmt::Error),
+            }
+        }
+    }
+
+    let args = [Celsius(100.0)];
+    let parsed = ParsedFormat::parse("{} {0:f} [{0:>8f}]", &args, &NoNamedArguments).unwrap();
+    assert_eq!("100°C 212°F [   212°F]", parsed.to_string());
+    assert!(ParsedFormat::parse("{:k}", &args, &NoNamedArguments).is_err());
+    assert!(ParsedFormat::parse("{:f}", &[Variant::Int(42)], &NoNamedArguments).is_err());
+}
+
+#[test]
+fn owned_format() {
+    use rt_format::{OwnedFormat, OwnedSegment};
+
+    struct Message {
+        format: OwnedFormat<Variant>,
+    }
+
+    let message = {
+        let format = String::from("{} is {:>5.1}!");
+        let args = vec![Variant::Str("e".to_string()), Variant::Float(2.5)];
+        let parsed = ParsedFormat::parse(&format, &args, &NoNamedArguments).unwrap();
+        Message { format: parsed.to_owned_format() }
+    };
+    assert_eq!("e is   2.5!", message.format.to_string());
+    assert_eq!(4, message.format.segments().len());
+    assert!(matches!(
+        &message.format.segments()[0],
+        OwnedSegment::Substitution { value, .. } if value == &Variant::Str("e".to_string())
+    ));
+}
+
+#[test]
+fn case_insensitive_named_arguments() {
+    use rt_format::argument::CaseInsensitiveArguments;
+
+    let named = CaseInsensitiveArguments([
+        ("name", Variant::Str("Ann".to_string())),
+        ("größe", Variant::Int(3)),
+    ]);
+    let format = "{Name} {NAME} {name} {größe}";
+    let parsed = ParsedFormat::parse(format, &NoPositionalArguments, &named).unwrap();
+    assert_eq!("Ann Ann Ann 3", parsed.to_string());
+    assert!(ParsedFormat::parse("{GRÖSSE}", &NoPositionalArguments, &named).is_err());
+}
+
+#[test]
+fn sign_flag() {
+    use rt_format::{Sign, Specifier, StdValue};
+
+    assert_eq!("+42 -42", fmt_args("{:+} {:+}", &[Variant::Int(42), Variant::Int(-42)]));
+    assert_eq!("+1.5 +0", fmt_args("{:+} {:+}", &[Variant::Float(1.5), Variant::Float(0.0)]));
+    let parsed = ParsedFormat::parse("{:+}", &[StdValue(7u32)], &NoNamedArguments).unwrap();
+    assert_eq!(format!("{:+}", 7u32), parsed.to_string());
+
+    // The underlying `Display` of a string ignores the flag, same as `format!` does.
+    assert_eq!(format!("{:+}", "abc"), fmt_args("{:+}", &[Variant::Str("abc".to_string())]));
+
+    struct Label(&'static str);
+    impl FormatArgument for Label {
+        fn supports_format(&self, spec: &Specifier) -> bool {
+            spec.sign == Sign::Default
+        }
+        fn fmt_display(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            fmt::Display::fmt(self.0, f)
+        }
+    }
+    assert!(ParsedFormat::parse("{}", &[Label("abc")], &NoNamedArguments).is_ok());
+    assert!(ParsedFormat::parse("{:+}", &[Label("abc")], &NoNamedArguments).is_err());
+}
+
+#[test]
+fn format_display_only() {
+    use rt_format::{format_display_only, Specifier};
+
+    struct Point(i32, i32);
+    impl fmt::Display for Point {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            f.pad(&format!("({}, {})", self.0, self.1))
+        }
+    }
+
+    struct Formatted<'a>(Specifier, &'a Point);
+    impl fmt::Display for Formatted<'_> {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            format_display_only(&self.0, self.1, f)
+        }
+    }
+
+    let point = Point(1, -2);
+    let spec = |text: &str| text.parse::<Specifier>().unwrap();
+    assert_eq!("(1, -2)", Formatted(spec(""), &point).to_string());
+    assert_eq!("*(1, -2)**", Formatted(spec("*^10"), &point).to_string());
+    assert_eq!("(1,", Formatted(spec(".3"), &point).to_string());
+
+    let mut out = String::new();
+    assert!(fmt::write(&mut out, format_args!("{}", Formatted(spec("?"), &point))).is_err());
+}
+
+#[test]
+fn format_value_without_pointer() {
+    use rt_format::{format_value, Specifier};
+
+    struct Formatted(Specifier, i32);
+    impl fmt::Display for Formatted {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            format_value(&self.0, &self.1, f)
+        }
+    }
+
+    let spec = |text: &str| text.parse::<Specifier>().unwrap();
+    assert_eq!("42", Formatted(Specifier::default(), 42).to_string());
+    assert_eq!("0x2a", Formatted(spec("#x"), 42).to_string());
+    assert_eq!("  4.2e1", Formatted(spec(">7e"), 42).to_string());
+
+    let mut out = String::new();
+    assert!(fmt::write(&mut out, format_args!("{}", Formatted(spec("p"), 42))).is_err());
+}
+
+#[test]
+fn sign_with_zero_padding() {
+    for value in [42, -42, 0] {
+        let args = [Variant::Int(value)];
+        assert_eq!(format!("{:+08}", value), fmt_args("{:+08}", &args));
+        assert_eq!(format!("{:+08x}", value), fmt_args("{:+08x}", &args));
+        assert_eq!(format!("{:+#08x}", value), fmt_args("{:+#08x}", &args));
+        assert_eq!(format!("{:<+8}", value), fmt_args("{:<+8}", &args));
+        assert_eq!(format!("{:08}", value), fmt_args("{:08}", &args));
+        assert_eq!(format!("{:<08}", value), fmt_args("{:<08}", &args));
+        assert_eq!(format!("{:^+08}", value), fmt_args("{:^+08}", &args));
+    }
+    let args = [Variant::Float(-1.5)];
+    assert_eq!(format!("{:+08.2}", -1.5), fmt_args("{:+08.2}", &args));
+    assert_eq!(format!("{:+010e}", -1.5), fmt_args("{:+010e}", &args));
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn or_else_named_arguments() {
+    use rt_format::argument::OrElse;
+
+    let mut request = HashMap::new();
+    request.insert("user", Variant::Str("ann".to_string()));
+    let mut defaults = HashMap::new();
+    defaults.insert("user", Variant::Str("guest".to_string()));
+    defaults.insert("lang", Variant::Str("en".to_string()));
+
+    let named = OrElse(request, defaults);
+    let parsed = ParsedFormat::parse("{user} {lang}", &NoPositionalArguments, &named).unwrap();
+    assert_eq!("ann en", parsed.to_string());
+    assert!(ParsedFormat::parse("{theme}", &NoPositionalArguments, &named).is_err());
+
+    let mut names = named.names();
+    names.sort();
+    assert_eq!(vec!["lang", "user"], names);
+}
+
+#[test]
+fn fn_named_arguments() {
+    use rt_format::argument::FnNamedArguments;
+
+    let (host, port) = (Variant::Str("example.com".to_string()), Variant::Int(8080));
+    let sizes = [Variant::Int(6), Variant::Int(2)];
+    let named = FnNamedArguments::new(|name: &str| match name {
+        "host" => Some(&host),
+        "port" => Some(&port),
+        "width" => sizes.first(),
+        "prec" => sizes.get(1),
+        _ => None,
+    });
+    let args = [Variant::Float(1.5)];
+    let parsed = ParsedFormat::parse("{host}:{port} [{:>width$.prec$}]", &args, &named);
+    assert_eq!("example.com:8080 [  1.50]", parsed.unwrap().to_string());
+    assert!(ParsedFormat::parse("{user}", &NoPositionalArguments, &named).is_err());
+    assert!(named.names().is_empty());
+
+    let template = rt_format::Template::parse("{port:#x}").unwrap();
+    assert_eq!("0x1f90", template.bind(&NoPositionalArguments, &named).unwrap().to_string());
+}
+
+#[test]
+fn exp_with_precision() {
+    for value in [12345, -12345, 0, 7, 999_999] {
+        let args = [Variant::Int(value)];
+        assert_eq!(format!("{:.0e}", value), fmt_args("{:.0e}", &args));
+        assert_eq!(format!("{:.2e}", value), fmt_args("{:.2e}", &args));
+        assert_eq!(format!("{:.3E}", value), fmt_args("{:.3E}", &args));
+        assert_eq!(format!("{:+12.1e}", value), fmt_args("{:+12.1e}", &args));
+        assert_eq!(format!("{:012.1E}", value), fmt_args("{:012.1E}", &args));
+    }
+    assert_eq!("1.23e4", fmt_args("{:.2e}", &[Variant::Int(12345)]));
+}
+
+#[test]
+fn exp_with_sign_and_zero_padding() {
+    for value in [1.5, -1.5, 0.0, -0.0, 12345.678, -0.000123] {
+        let args = [Variant::Float(value)];
+        assert_eq!(format!("{:08.2e}", value), fmt_args("{:08.2e}", &args));
+        assert_eq!(format!("{:+08.2e}", value), fmt_args("{:+08.2e}", &args));
+        assert_eq!(format!("{:08e}", value), fmt_args("{:08e}", &args));
+        assert_eq!(format!("{:+012E}", value), fmt_args("{:+012E}", &args));
+        assert_eq!(format!("{:>+012.1e}", value), fmt_args("{:>+012.1e}", &args));
+        assert_eq!(format!("{:*<+12.1e}", value), fmt_args("{:*<+12.1e}", &args));
+    }
+    assert_eq!("-01.50e0", fmt_args("{:08.2e}", &[Variant::Float(-1.5)]));
+    assert_eq!("+01.50e0", fmt_args("{:+08.2e}", &[Variant::Float(1.5)]));
+}
+
+#[test]
+fn positional_argument_containers() {
+    let array = [Variant::Int(1), Variant::Int(2)];
+    let parsed = ParsedFormat::parse("{} {}", &array, &NoNamedArguments).unwrap();
+    assert_eq!("1 2", parsed.to_string());
+
+    let vec = vec![Variant::Int(3), Variant::Int(4)];
+    let parsed = ParsedFormat::parse("{} {}", &vec, &NoNamedArguments).unwrap();
+    assert_eq!("3 4", parsed.to_string());
+
+    let boxed: Box<[Variant]> = vec![Variant::Int(5), Variant::Int(6)].into_boxed_slice();
+    let parsed = ParsedFormat::parse("{} {}", &boxed, &NoNamedArguments).unwrap();
+    assert_eq!("5 6", parsed.to_string());
+}
+
+#[test]
+fn next_arguments() {
+    use rt_format::argument::NextArguments;
+
+    let values = [Variant::Int(1), Variant::Str("two".to_string())];
+    let mut calls = 0;
+    let args = NextArguments::new(|| {
+        calls += 1;
+        values.get(calls - 1)
+    });
+
+    let parsed = ParsedFormat::parse("{} and {:>5}", &args, &NoNamedArguments).unwrap();
+    assert_eq!("1 and   two", parsed.to_string());
+    assert!(ParsedFormat::parse("{}", &args, &NoNamedArguments).is_err());
+    let first = NextArguments::new(|| values.first());
+    assert!(ParsedFormat::parse("{0}", &first, &NoNamedArguments).is_err());
+}
+
+#[test]
+fn natural_alignment() {
+    use rt_format::{Align, Format, Specifier};
+
+    let args = [Variant::Int(42), Variant::Float(2.5), Variant::Str("ab".to_string())];
+    assert_eq!(
+        format!("[{:5}] [{:6}] [{:5}]", 42, 2.5, "ab"),
+        fmt_args("[{:5}] [{:6}] [{:5}]", &args)
+    );
+    assert_eq!(
+        format!("[{:*<5}] [{:^6}] [{:>5}]", 42, 2.5, "ab"),
+        fmt_args("[{:*<5}] [{:^6}] [{:>5}]", &args)
+    );
+    assert_eq!(format!("[{:05}] [{:+6}]", 42, 2.5), fmt_args("[{:05}] [{:+6}]", &args));
+
+    // Pads its digits with `Formatter::pad`, which aligns to the left unless told otherwise.
+    struct Digits(&'static str, Align);
+    impl FormatArgument for Digits {
+        fn supports_format(&self, spec: &Specifier) -> bool {
+            spec.format == Format::Display
+        }
+        fn fmt_display(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            f.pad(self.0)
+        }
+        fn natural_alignment(&self) -> Align {
+            self.1
+        }
+    }
+
+    let args = [Digits("42", Align::Right), Digits("42", Align::Left), Digits("42", Align::None)];
+    let parsed = ParsedFormat::parse("[{:5}] [{:5}] [{:5}]", &args, &NoNamedArguments).unwrap();
+    assert_eq!(format!("[{:5}] [{:5}] [{:5}]", 42, "42", "42"), parsed.to_string());
+
+    struct Unaligned;
+    impl FormatArgument for Unaligned {}
+    assert_eq!(Align::None, Unaligned.natural_alignment());
+}
+
+#[test]
+fn format_into_string() {
+    let args = [Variant::Int(42), Variant::Str("ab".to_string())];
+    let parsed = ParsedFormat::parse("x = {:>6}, y = {}!", &args, &NoNamedArguments).unwrap();
+    assert_eq!(17, parsed.estimated_len());
+    let formatted = parsed.format_into_string();
+    assert_eq!(parsed.to_string(), formatted);
+    assert!(formatted.capacity() >= parsed.estimated_len());
+    assert!(formatted.len() >= parsed.estimated_len());
+}
+
+#[test]
+fn display_with_specifier() {
+    use rt_format::{Format, Specifier, Width};
+
+    struct Weekday(&'static str);
+
+    impl FormatArgument for Weekday {
+        fn supports_format(&self, specifier: &Specifier) -> bool {
+            specifier.format == Format::Display
+        }
+
+        fn fmt_display(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            f.pad(self.0)
+        }
+
+        fn fmt_display_with(&self, specifier: &Specifier, f: &mut fmt::Formatter) -> fmt::Result {
+            match specifier.width {
+                Width::AtLeast { width } if width < self.0.len() => f.pad(&self.0[..3]),
+                _ => self.fmt_display(f),
+            }
+        }
+    }
+
+    let args = [Weekday("Monday")];
+    let format = |spec| ParsedFormat::parse(spec, &args, &NoNamedArguments).unwrap().to_string();
+    assert_eq!("Monday", format("{}"));
+    assert_eq!("[Monday  ]", format("[{:8}]"));
+    assert_eq!("[Mon ]", format("[{:4}]"));
+    assert_eq!("[*Mon*]", format("[{:*^5}]"));
+
+    // The options of the formatter that a segment is written to don't reach the argument.
+    let parsed = ParsedFormat::parse("{}{0:?}", &[Variant::Int(42)], &NoNamedArguments).unwrap();
+    let segments = parsed.segments();
+    assert_eq!("[42][Int(42)]", format!("[{:>8}][{:<8}]", segments[0], segments[1]));
+}
+
+#[test]
+fn to_cow() {
+    use std::borrow::Cow;
+
+    let format = String::from("no placeholders here");
+    let parse = |format| {
+        ParsedFormat::<Variant>::parse(format, &NoPositionalArguments, &NoNamedArguments).unwrap()
+    };
+    assert!(matches!(parse(&format).to_cow(), Cow::Borrowed(text) if text == format));
+    assert!(matches!(parse("").to_cow(), Cow::Borrowed("")));
+    assert!(matches!(parse("{{literal}}").to_cow(), Cow::Owned(text) if text == "{literal}"));
+
+    let args = [Variant::Int(42)];
+    let parsed = ParsedFormat::parse("x = {}", &args, &NoNamedArguments).unwrap();
+    assert!(matches!(parsed.to_cow(), Cow::Owned(text) if text == "x = 42"));
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn missing_argument_passthrough() {
+    use rt_format::parser::MissingBehavior;
+    use rt_format::Template;
+
+    let mut named = HashMap::new();
+    named.insert("user", Variant::Str("ann".to_string()));
+    named.insert("width", Variant::Int(6));
+    let positional = [Variant::Int(3)];
+    let parse = |format, missing| {
+        let parser = Parser::new(format, &positional, &named).with_missing_behavior(missing);
+        let parsed = ParsedFormat::from_parser(parser);
+        parsed.map(|parsed| parsed.to_string())
+    };
+
+    let format = "{user:>width$} has {} of {} {item:w$}, {{{2}}}";
+    assert_eq!(
+        Ok("   ann has 3 of {} {item:w$}, {{{2}}}".to_string()),
+        parse(format, MissingBehavior::Passthrough)
+    );
+    assert!(parse(format, MissingBehavior::Error).is_err());
+    assert!(parse("{user:x}", MissingBehavior::Passthrough).is_err());
+
+    let template = Template::parse("{user}: {count:.prec$}").unwrap();
+    let first = template
+        .with_missing_behavior(MissingBehavior::Passthrough)
+        .bind(&NoPositionalArguments, &named)
+        .unwrap()
+        .to_string();
+    assert_eq!("ann: {count:.prec$}", first);
+    let second = [("count", Variant::Float(2.5)), ("prec", Variant::Int(2))];
+    assert_eq!("ann: 2.50", fmt_args_map(&first, &[], &second));
+
+    let named = [("a", Variant::Int(1))];
+    let parser = Parser::new("{{lit}} {a} {b}", &NoPositionalArguments, &named)
+        .with_missing_behavior(MissingBehavior::Passthrough);
+    let first = ParsedFormat::from_parser(parser).unwrap().to_string();
+    assert_eq!("{{lit}} 1 {b}", first);
+    assert_eq!("{lit} 1 2", fmt_args_map(&first, &[], &[("b", Variant::Int(2))]));
+}
+
+#[test]
+fn fallback_values() {
+    use rt_format::{OwnedTemplate, Template};
+
+    let present = [("host", Variant::Str("example.org".to_string())), ("port", Variant::Int(80))];
+    let absent: [(&str, Variant); 0] = [];
+    let format = "{host?localhost}:{port?8080:>4}/{path?}";
+    assert_eq!("example.org:  80/", fmt_args_map(format, &[], &present));
+    assert_eq!("localhost:8080/", fmt_args_map(format, &[], &absent));
+
+    let positional = [Variant::Int(1)];
+    assert_eq!("1 two 1 -", fmt_args_map("{0?one} {1?two} {?-} {?-}", &positional, &[]));
+    assert_eq!("[x] 1", fmt_args_map("[{a?x:>w$}] {}", &positional, &[]));
+    let parse = |format| ParsedFormat::parse(format, &positional, &NoNamedArguments);
+    assert!(parse("{a:x?y}").is_err());
+    assert!(parse("{a?b{}").is_err());
+
+    let parser = Parser::new("{ a ? no value : >4 }|", &positional, &absent).with_trimmed_names();
+    let parsed = ParsedFormat::from_parser(parser);
+    assert_eq!("no value|", parsed.unwrap().to_string());
+
+    let template = Template::parse("{host?local host}").unwrap();
+    let parsed = template.bind(&NoPositionalArguments, &absent).unwrap();
+    assert_eq!("local host", parsed.to_string());
+    assert_eq!(0..17, parsed.segments()[0].span());
+    let owned = OwnedTemplate::parse("<{host?none}>").unwrap();
+    assert_eq!("<none>", owned.bind(&NoPositionalArguments, &absent).unwrap().to_string());
+}
+
+#[test]
+fn write_macros() {
+    use std::fmt::Write as _;
+    use std::io::Write as _;
+
+    let args = [Variant::Int(42), Variant::Str("x".to_string())];
+    let parsed = ParsedFormat::parse("{:>4}|{}", &args, &NoNamedArguments).unwrap();
+
+    let mut out = Vec::new();
+    write!(out, "<{}>", parsed).unwrap();
+    writeln!(out, "<{}>", parsed.as_display()).unwrap();
+    assert_eq!(b"<  42|x><  42|x>\n", out.as_slice());
+
+    let mut text = String::new();
+    for segment in parsed.segments() {
+        if let rt_format::parser::Segment::Substitution(sub) = segment {
+            writeln!(text, "{}", sub).unwrap();
+        }
+    }
+    assert_eq!("  42\nx\n", text);
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn scope_named_arguments() {
+    use rt_format::argument::Scope;
+
+    let mut outer = Scope::new();
+    outer.set("x", Variant::Int(1));
+    outer.set("unit", Variant::Str("cm".to_string()));
+    let mut inner = outer.child();
+    inner.set("x", Variant::Int(2));
+    let snapshot = inner.clone();
+    inner.set("y", Variant::Float(0.5));
+
+    let format = "{x}{unit} {y:.1}";
+    let parsed = ParsedFormat::parse(format, &NoPositionalArguments, &inner).unwrap();
+    assert_eq!("2cm 0.5", parsed.to_string());
+    let parsed = ParsedFormat::parse("{x}{unit}", &NoPositionalArguments, &outer).unwrap();
+    assert_eq!("1cm", parsed.to_string());
+    assert!(ParsedFormat::parse("{y}", &NoPositionalArguments, &snapshot).is_err());
+
+    let mut names = inner.names();
+    names.sort();
+    assert_eq!(vec!["unit", "x", "y"], names);
+    assert_eq!(Some(Variant::Int(2)), inner.set("x", Variant::Int(3)));
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn smart_pointer_arguments() {
+    use std::collections::BTreeMap;
+    use std::rc::Rc;
+    use std::sync::Arc;
+
+    let shared = vec![Arc::new(Variant::Int(42)), Arc::new(Variant::Str("x".to_string()))];
+    let parsed = ParsedFormat::parse("{1}{:>4}{}", &shared, &NoNamedArguments).unwrap();
+    assert_eq!("x  42x", parsed.to_string());
+
+    let mut named = HashMap::new();
+    named.insert("n", Arc::clone(&shared[0]));
+    assert_eq!(
+        "0x2a",
+        ParsedFormat::parse("{n:#x}", &NoPositionalArguments, &named).unwrap().to_string()
+    );
+
+    let mut named = BTreeMap::new();
+    named.insert("s", Rc::new(Variant::Str("y".to_string())));
+    named.insert("b", Rc::new(Variant::Float(1.5)));
+    let positional = [Rc::new(Variant::Int(3))];
+    assert_eq!(
+        "y 1.5 3",
+        ParsedFormat::parse("{s} {b} {:.0$}", &positional, &named).unwrap().to_string()
+    );
+}
+
+#[test]
+fn reference_arguments() {
+    let values = [Variant::Int(42), Variant::Str("x".to_string())];
+    let refs: Vec<&Variant> = values.iter().collect();
+    let args: &[&Variant] = &refs;
+    let parsed = ParsedFormat::parse("{1}{0:>4}{0:#x}", args, &NoNamedArguments).unwrap();
+    assert_eq!("x  420x2a", parsed.to_string());
+
+    let nested: Vec<&&Variant> = refs.iter().collect();
+    assert_eq!(
+        "[x   ]",
+        ParsedFormat::parse("[{1:4}]", &nested, &NoNamedArguments).unwrap().to_string()
+    );
+}
+
+#[test]
+fn debug_indented() {
+    use rt_format::{Format, Specifier};
+
+    struct Items(Vec<i32>);
+
+    impl FormatArgument for Items {
+        fn supports_format(&self, specifier: &Specifier) -> bool {
+            specifier.format == Format::Debug
+        }
+
+        fn fmt_debug(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            fmt::Debug::fmt(&self.0, f)
+        }
+
+        fn fmt_debug_indented(&self, indent: usize, f: &mut fmt::Formatter) -> fmt::Result {
+            let margin = format!("\n{:1$}", "", indent);
+            f.write_str(&format!("{:#?}", self.0).replace('\n', &margin))
+        }
+    }
+
+    let args = [Items(vec![1, 2])];
+    let format = |spec| ParsedFormat::parse(spec, &args, &NoNamedArguments).unwrap().to_string();
+    assert_eq!("[\n        1,\n        2,\n    ]", format("{:#4?}"));
+    assert_eq!(format!("{:#?}", args[0].0), format("{:#?}"));
+    assert_eq!(format!("{:?}", args[0].0), format("{:?}"));
+
+    let args = [Variant::Str("a\nb".to_string()), Variant::Int(42)];
+    assert_eq!(format!("{:#8?}|{:#6?}", args[0], args[1]), fmt_args("{:#8?}|{:#6?}", &args));
+}
+
+#[cfg(feature = "unicode-width")]
+#[test]
+fn unicode_width_padding() {
+    use rt_format::{Measure, Template};
+
+    let columns = |format, args: &[Variant]| {
+        let parser = Parser::new(format, args, &NoNamedArguments).with_measure(Measure::Columns);
+        ParsedFormat::from_parser(parser).unwrap().to_string()
+    };
+    let args = [Variant::Str("漢字".to_string()), Variant::Str("e\u{301}".to_string())];
+    assert_eq!("[漢字  ] [  漢字] [漢字 ]", columns("[{0:6}] [{0:>6}] [{0:^5}]", &args));
+    assert_eq!("[*漢字*] [e\u{301}  ]", columns("[{0:*^6}] [{1:3}]", &args));
+    assert_eq!("[漢 ] [漢…]", columns("[{0:<3~3}] [{0:…3}]", &args));
+    assert_eq!(format!("[{:6?}]", args[0]), columns("[{0:6?}]", &args));
+    assert_eq!("[漢字    ] [e\u{301} ]", fmt_args("[{0:6}] [{1:3}]", &args));
+
+    let template = Template::parse("[{:>6}]").unwrap().with_measure(Measure::Columns);
+    assert_eq!("[  漢字]", template.bind(&args, &NoNamedArguments).unwrap().to_string());
+
+    let args = [Variant::Int(1234)];
+    assert_eq!("[  1,234] [1,234  ]", columns("[{0:7,}] [{0:<7,}]", &args));
+}
+
+#[test]
+fn locale_separators() {
+    use rt_format::{Locale, Template};
+
+    let german = Locale {
+        decimal_separator: ',',
+        grouping_separator: Some('.'),
+        grouping_size: 3,
+    };
+    let fmt_localized = |format: &str, args: &[Variant]| {
+        ParsedFormat::from_parser(Parser::new(format, args, &NoNamedArguments).with_locale(german))
+            .unwrap()
+            .to_string()
+    };
+    let args = [Variant::Float(-1234567.25), Variant::Int(1234), Variant::Str("v1.5".to_string())];
+    assert_eq!("-1.234.567,25", fmt_localized("{}", &args));
+    assert_eq!("-1.234.567,2", fmt_localized("{:.1}", &args));
+    assert_eq!("[       1.234]", fmt_localized("[{1:12}]", &args));
+    assert_eq!("[*1.234*]", fmt_localized("[{1:*^7}]", &args));
+    assert_eq!("+0001.234", fmt_localized("{1:+09}", &args));
+    assert_eq!("-1_234_567,25", fmt_localized("{:_}", &args));
+    assert_eq!("4d2 v1.5", fmt_localized("{1:x} {2}", &args));
+    assert_eq!("-1.2346e6", fmt_localized("{:.4e}", &args));
+
+    let no_grouping = Locale { grouping_separator: None, ..german };
+    let parser = Parser::new("{}", &args, &NoNamedArguments).with_locale(no_grouping);
+    let parsed = ParsedFormat::from_parser(parser);
+    assert_eq!("-1234567,25", parsed.unwrap().to_string());
+
+    let template = Template::parse("{:.2} {:.2}").unwrap().with_locale(german);
+    let args = [Variant::Float(0.5), Variant::Float(f64::NAN)];
+    let parsed = template.bind(&args, &NoNamedArguments).unwrap();
+    assert_eq!("0,50 NaN", parsed.to_string());
+    assert_eq!("0.50", fmt_args("{:.2}", &args));
+}
+
+#[test]
+fn locale_numbers_only() {
+    use rt_format::Locale;
+
+    let german = Locale { decimal_separator: ',', grouping_separator: Some('.'), grouping_size: 3 };
+    let year = Variant::Str("2024".to_string());
+    let args = [year, Variant::Str("12.5".to_string()), Variant::Int(2024)];
+    let parser = Parser::new("{} {} {:>6} {0:,}", &args, &NoNamedArguments).with_locale(german);
+    assert_eq!("2024 12.5  2.024 2024", ParsedFormat::from_parser(parser).unwrap().to_string());
+}
+
+#[test]
+fn duration_values() {
+    use rt_format::argument::DurationValue;
+    use std::time::Duration;
+
+    let format = |format: &str, duration: Duration| {
+        let args = [DurationValue(duration)];
+        ParsedFormat::parse(format, &args, &NoNamedArguments).map(|parsed| parsed.to_string())
+    };
+    let duration = Duration::new(90, 250_000_000);
+    assert_eq!(Ok("90.25 90.250 [  90.2]".to_string()), format("{} {0:.3} [{0:6.1}]", duration));
+    assert_eq!(Ok(format!("{:?} {:>8?}", duration, duration)), format("{:?} {0:>8?}", duration));
+    assert_eq!(
+        Ok("1m30s [   1m30s] 1m30s__".to_string()),
+        format("{:h} [{0:8h}] {0:_<7h}", duration)
+    );
+    assert!(format("{:x}", duration).is_err());
+    assert!(format("{:m}", duration).is_err());
+
+    let human = |duration| format("{:h}", duration).unwrap();
+    assert_eq!("0s", human(Duration::ZERO));
+    assert_eq!("17ns", human(Duration::from_nanos(17)));
+    assert_eq!("1µs", human(Duration::from_nanos(1_999)));
+    assert_eq!("250ms", human(Duration::from_millis(250)));
+    assert_eq!("1s", human(Duration::from_millis(1_999)));
+    assert_eq!("2h5s", human(Duration::from_secs(7_205)));
+    assert_eq!("26h", human(Duration::from_secs(93_600)));
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn duration_from_system_time() {
+    use rt_format::argument::DurationValue;
+    use std::convert::TryFrom;
+    use std::time::{Duration, SystemTime};
+
+    let duration = Duration::new(90, 250_000_000);
+    let time = SystemTime::UNIX_EPOCH + duration;
+    assert_eq!(DurationValue(duration), DurationValue::try_from(time).unwrap());
+    assert!(DurationValue::try_from(SystemTime::UNIX_EPOCH - duration).is_err());
+}
+
+#[test]
+fn unsupported_formatter() {
+    use rt_format::{OwnedTemplate, Specifier, Template};
+
+    let args = [Variant::Float(2.5), Variant::Str("abc".to_string())];
+    let unsupported = |specifier: &Specifier, value: &Variant, f: &mut fmt::Formatter| {
+        write!(f, "<{:?} as {}>", value, specifier.format)
+    };
+    let parse = |format| {
+        let parser = Parser::new(format, &args, &NoNamedArguments);
+        ParsedFormat::from_parser(parser.with_unsupported_formatter(&unsupported))
+    };
+    let parsed = parse("{:x} {:>4} {1:b}").unwrap();
+    assert_eq!("<Float(2.5) as x>  abc <Str(\"abc\") as b>", parsed.to_string());
+    assert_eq!(parsed.to_string(), parsed.to_owned_format().to_string());
+    assert!(parse("{2}").is_err());
+    assert!(ParsedFormat::parse("{:x}", &args, &NoNamedArguments).is_err());
+
+    let segments: Result<Vec<_>, _> = Parser::new("{0:?}|{0:o}", &args, &NoNamedArguments)
+        .with_unsupported_formatter(&|_, _, f| f.write_str("?"))
+        .collect();
+    let parsed: Vec<_> = segments.unwrap().iter().map(|segment| segment.to_string()).collect();
+    assert_eq!(vec!["Float(2.5)", "|", "?"], parsed);
+
+    let template = Template::parse("{:x} {1}").unwrap();
+    let parser = template.parser(&args, &NoNamedArguments).with_unsupported_formatter(&unsupported);
+    assert_eq!("<Float(2.5) as x> abc", ParsedFormat::from_parser(parser).unwrap().to_string());
+    let owned = OwnedTemplate::parse("{1:b}").unwrap();
+    let parser = owned.parser(&args, &NoNamedArguments).with_unsupported_formatter(&unsupported);
+    assert_eq!("<Str(\"abc\") as b>", ParsedFormat::from_parser(parser).unwrap().to_string());
+}
+
+#[test]
+fn combined_parser_options() {
+    use rt_format::parser::MissingBehavior;
+    use rt_format::Locale;
+
+    let locale = Locale { decimal_separator: ',', grouping_separator: None, grouping_size: 3 };
+    let args = [Variant::Float(2.5), Variant::Int(3)];
+    let parse = |format| {
+        let parser = Parser::new(format, &args, &NoNamedArguments);
+        ParsedFormat::from_parser(parser.with_locale(locale).with_strict_arguments())
+    };
+    assert_eq!("2,5 3", parse("{} {}").unwrap().to_string());
+    assert!(parse("{}").is_err());
+
+    let named = [("a", Variant::Int(1))];
+    let parser = Parser::new("{ a } {b}", &NoPositionalArguments, &named)
+        .with_trimmed_names()
+        .with_missing_behavior(MissingBehavior::Passthrough);
+    assert_eq!("1 {b}", ParsedFormat::from_parser(parser).unwrap().to_string());
+}
+
+#[test]
+fn string_values() {
+    use std::borrow::Cow;
+
+    let owned = ["apples".to_string(), "6".to_string()];
+    assert_eq!(
+        Ok(format!("[{:.3}] [{:>8}] [{:?}] [{:^6.2}]", "apples", "apples", "apples", "apples")),
+        ParsedFormat::parse("[{:.3}] [{0:>8}] [{0:?}] [{0:^6.2}]", &owned, &NoNamedArguments)
+            .map(|parsed| parsed.to_string())
+    );
+    assert_eq!(
+        Ok("[apples]".to_string()),
+        ParsedFormat::parse("[{:>1$}]", &owned, &NoNamedArguments).map(|parsed| parsed.to_string())
+    );
+    assert!(ParsedFormat::parse("{:x}", &owned, &NoNamedArguments).is_err());
+    assert!(ParsedFormat::parse("{:0$}", &owned, &NoNamedArguments).is_err());
+
+    let slices = ["pear", "12"];
+    assert_eq!(
+        Ok("        pear".to_string()),
+        ParsedFormat::parse("{:>1$}", &slices, &NoNamedArguments).map(|parsed| parsed.to_string())
+    );
+
+    let cows: [Cow<str>; 2] = [Cow::Borrowed("fig"), Cow::Owned("plum".to_string())];
+    assert_eq!(
+        Ok("fig_ \"plum\"".to_string()),
+        ParsedFormat::parse("{:_<4} {:?}", &cows, &NoNamedArguments)
+            .map(|parsed| parsed.to_string())
+    );
+}
@@ -0,0 +1,142 @@
+use std::convert::TryFrom;
+use std::fmt;
+
+use proptest::prelude::*;
+use rt_format::{Align, Format, FormatArgument, ParsedFormat, Specifier};
+
+/// Pairs each formatting string with a closure that formats it with `format!`, so that the same
+/// string can be checked against this crate. The width and precision are taken from the `w` and
+/// `p` arguments, which lets them vary while the rest of the specifier is fixed.
+macro_rules! cases {
+    (|$value:ident: $type:ty, $w:ident, $p:ident| $($format:literal),+ $(,)?) => {
+        &[$(
+            ($format, |$value: $type, $w: usize, $p: usize| {
+                let _ = ($w, $p);
+                format!($format, $value)
+            }),
+        )+]
+    };
+}
+
+type Case<T> = (&'static str, fn(T, usize, usize) -> String);
+
+const INT_CASES: &[Case<i64>] = cases!(|value: i64, w, p|
+    "{}", "{:w$}", "{:<w$}", "{:^w$}", "{:>w$}", "{:*<w$}", "{:*^w$}", "{:->w$}", "{:+}",
+    "{:+w$}", "{:0w$}", "{:+0w$}", "{:<0w$}", "{:^+0w$}", "{:*^+w$}", "{:x}", "{:#x}",
+    "{:#0w$x}", "{:+#0w$X}", "{:_>#w$x}", "{:o}", "{:#w$o}", "{:b}", "{:#0w$b}", "{:e}",
+    "{:.p$e}", "{:+0w$.p$E}", "{:?}", "{:w$?}", "{:#?}", "{:.p$}", "{:w$.p$}", "{:é^w$}",
+);
+
+const FLOAT_CASES: &[Case<f64>] = cases!(|value: f64, w, p|
+    "{}", "{:w$}", "{:.p$}", "{:w$.p$}", "{:+.p$}", "{:0w$.p$}", "{:+0w$.p$}", "{:*^w$.p$}",
+    "{:<w$.p$}", "{:->+w$}", "{:e}", "{:.p$e}", "{:+0w$.p$E}", "{:*<w$e}", "{:?}", "{:.p$?}",
+    "{:#?}", "{:0w$?}", "{:→>w$.p$}",
+);
+
+/// The value to format. Unlike the `Variant` of the other tests, its debug format is the debug
+/// format of the wrapped number, so that it matches `format!`.
+#[derive(Debug, Clone, Copy)]
+enum Value {
+    Int(i64),
+    Float(f64),
+}
+
+impl FormatArgument for Value {
+    fn supports_format(&self, spec: &Specifier) -> bool {
+        match self {
+            Value::Int(_) => !matches!(spec.format, Format::Pointer | Format::Custom(_)),
+            Value::Float(_) => matches!(
+                spec.format,
+                Format::Display | Format::Debug | Format::LowerExp | Format::UpperExp
+            ),
+        }
+    }
+
+    fn fmt_display(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Value::Int(val) => fmt::Display::fmt(val, f),
+            Value::Float(val) => fmt::Display::fmt(val, f),
+        }
+    }
+
+    fn fmt_debug(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Value::Int(val) => fmt::Debug::fmt(val, f),
+            Value::Float(val) => fmt::Debug::fmt(val, f),
+        }
+    }
+
+    fn fmt_octal(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Value::Int(val) => fmt::Octal::fmt(val, f),
+            Value::Float(_) => Err(fmt::Error),
+        }
+    }
+
+    fn fmt_lower_hex(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Value::Int(val) => fmt::LowerHex::fmt(val, f),
+            Value::Float(_) => Err(fmt::Error),
+        }
+    }
+
+    fn fmt_upper_hex(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Value::Int(val) => fmt::UpperHex::fmt(val, f),
+            Value::Float(_) => Err(fmt::Error),
+        }
+    }
+
+    fn fmt_binary(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Value::Int(val) => fmt::Binary::fmt(val, f),
+            Value::Float(_) => Err(fmt::Error),
+        }
+    }
+
+    fn fmt_lower_exp(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Value::Int(val) => fmt::LowerExp::fmt(val, f),
+            Value::Float(val) => fmt::LowerExp::fmt(val, f),
+        }
+    }
+
+    fn fmt_upper_exp(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Value::Int(val) => fmt::UpperExp::fmt(val, f),
+            Value::Float(val) => fmt::UpperExp::fmt(val, f),
+        }
+    }
+
+    fn natural_alignment(&self) -> Align {
+        Align::Right
+    }
+
+    fn to_usize(&self) -> Result<usize, ()> {
+        match self {
+            Value::Int(val) => usize::try_from(*val).map_err(|_| ()),
+            Value::Float(_) => Err(()),
+        }
+    }
+}
+
+fn format_rt(format: &str, value: Value, w: usize, p: usize) -> String {
+    let named = [("w", Value::Int(w as i64)), ("p", Value::Int(p as i64))];
+    ParsedFormat::parse(format, &[value], &named).unwrap().to_string()
+}
+
+proptest! {
+    #[test]
+    fn int_parity(case in 0..INT_CASES.len(), value: i64, w in 0usize..30, p in 0usize..12) {
+        let (format, std_format) = INT_CASES[case];
+        let rt = format_rt(format, Value::Int(value), w, p);
+        prop_assert_eq!(std_format(value, w, p), rt, "{}", format);
+    }
+
+    #[test]
+    fn float_parity(case in 0..FLOAT_CASES.len(), value: f64, w in 0usize..30, p in 0usize..12) {
+        let (format, std_format) = FLOAT_CASES[case];
+        let rt = format_rt(format, Value::Float(value), w, p);
+        prop_assert_eq!(std_format(value, w, p), rt, "{}", format);
+    }
+}
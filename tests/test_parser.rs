@@ -1,15 +1,21 @@
+#[cfg(feature = "std")]
 use std::collections::HashMap;
 
 use rt_format::argument::{
-    ArgumentSource, NamedArguments, NoNamedArguments, NoPositionalArguments, PositionalArguments
+    ArgumentSource, Counting, FnNamedArguments, NamedArguments, NextArguments, NoNamedArguments,
+    NoPositionalArguments, PositionalArguments
+};
+use rt_format::parser::{parse_specifier, parse_specifier_str, ParseError, Parser, SyntaxOptions};
+use rt_format::parser::Segment;
+use rt_format::{
+    Align, ArgumentRef, Format, FormatLimits, Grouping, Locale, MaxWidth, Pad, ParseErrorKind,
+    ParsedFormat, Precision, Repr, Sign, Specifier, Template, Width
 };
-use rt_format::parser::{parse_specifier};
-use rt_format::{Align, ParsedFormat, Format, Pad, Precision, Repr, Sign, Specifier, Width};
 
 mod common;
 use common::Variant;
 
-type ParseResult<'a> = Result<ParsedFormat<'a, Variant>, usize> ;
+type ParseResult<'a> = Result<ParsedFormat<'a, Variant>, ParseError>;
 
 fn parse<'a, P, N>(format: &'a str, positional: &'a P, named: &'a N) -> ParseResult<'a>
 where
@@ -19,10 +25,28 @@ where
     ParsedFormat::parse(format, positional, named)
 }
 
+fn strict<'a, P, N>(format: &'a str, positional: &'a P, named: &'a N) -> ParseResult<'a>
+where
+    P: PositionalArguments<'a, Variant>,
+    N: NamedArguments<Variant>,
+{
+    ParsedFormat::from_parser(Parser::new(format, positional, named).with_strict_arguments())
+}
+
+fn parse_err<'a, P, N>(format: &'a str, positional: &'a P, named: &'a N) -> (usize, ParseErrorKind)
+where
+    P: PositionalArguments<'a, Variant>,
+    N: NamedArguments<Variant>,
+{
+    let err = parse(format, positional, named).unwrap_err();
+    (err.offset(), err.kind().clone())
+}
+
 #[test]
 fn unmatched_brace() {
-    assert_eq!(Err(4), parse("foo {", &NoPositionalArguments, &NoNamedArguments));
-    assert_eq!(Err(4), parse("bar } baz", &NoPositionalArguments, &NoNamedArguments));
+    let unmatched = (4, ParseErrorKind::UnmatchedBrace);
+    assert_eq!(unmatched, parse_err("foo {", &NoPositionalArguments, &NoNamedArguments));
+    assert_eq!(unmatched, parse_err("bar } baz", &NoPositionalArguments, &NoNamedArguments));
 }
 
 #[test]
@@ -38,16 +62,16 @@ fn escaped_braces() {
 #[test]
 fn invalid_specifier() {
     assert_eq!(
-        Err(4),
-        parse("foo {:Z} bar", &[Variant::Int(42)], &NoNamedArguments)
+        (4, ParseErrorKind::InvalidSpecifier),
+        parse_err("foo {:%} bar", &[Variant::Int(42)], &NoNamedArguments)
     );
 }
 
 #[test]
 fn invalid_arg_position() {
     assert_eq!(
-        Err(4),
-        parse("foo {0bar} baz", &[Variant::Int(42)], &NoNamedArguments)
+        (4, ParseErrorKind::InvalidSpecifier),
+        parse_err("foo {0bar} baz", &[Variant::Int(42)], &NoNamedArguments)
     );
 }
 
@@ -71,6 +95,7 @@ fn positional_arg_lookup() {
     );
 }
 
+#[cfg(feature = "std")]
 #[test]
 fn named_arg_lookup() {
     let mut map = HashMap::new();
@@ -86,80 +111,169 @@ fn named_arg_lookup() {
 #[test]
 fn missing_next_arg() {
     assert_eq!(
-        Err(3),
-        parse("{} {}", &[Variant::Int(42)], &NoNamedArguments)
+        (3, ParseErrorKind::ArgumentIndexOutOfRange { index: 1, available: 1 }),
+        parse_err("{} {}", &[Variant::Int(42)], &NoNamedArguments)
     );
 }
 
 #[test]
 fn missing_positional_arg() {
-    assert_eq!(Err(0), parse("{1}", &[Variant::Int(42)], &NoNamedArguments));
+    assert_eq!(
+        (0, ParseErrorKind::ArgumentIndexOutOfRange { index: 1, available: 1 }),
+        parse_err("{1}", &[Variant::Int(42)], &NoNamedArguments)
+    );
 }
 
 #[test]
 fn missing_named_arg() {
-    assert_eq!(Err(0), parse("{arglebargle}", &NoPositionalArguments, &NoNamedArguments));
+    assert_eq!(
+        (0, ParseErrorKind::MissingNamedArgument { name: "arglebargle".to_string() }),
+        parse_err("{arglebargle}", &NoPositionalArguments, &NoNamedArguments)
+    );
 }
 
 #[test]
 fn missing_positional_width() {
     assert_eq!(
-        Err(0),
-        parse("{:1$}", &[Variant::Int(42)], &NoNamedArguments)
+        (0, ParseErrorKind::ArgumentIndexOutOfRange { index: 1, available: 1 }),
+        parse_err("{:1$}", &[Variant::Int(42)], &NoNamedArguments)
     );
 }
 
 #[test]
 fn missing_named_width() {
     assert_eq!(
-        Err(0),
-        parse("{:arglebargle$}", &[Variant::Int(42)], &NoNamedArguments)
+        (0, ParseErrorKind::MissingNamedArgument { name: "arglebargle".to_string() }),
+        parse_err("{:arglebargle$}", &[Variant::Int(42)], &NoNamedArguments)
     );
 }
 
 #[test]
 fn missing_positional_precision() {
     assert_eq!(
-        Err(0),
-        parse("{:.1$}", &[Variant::Int(42)], &NoNamedArguments)
+        (0, ParseErrorKind::ArgumentIndexOutOfRange { index: 1, available: 1 }),
+        parse_err("{:.1$}", &[Variant::Int(42)], &NoNamedArguments)
     );
 }
 
 #[test]
 fn missing_named_precision() {
     assert_eq!(
-        Err(0),
-        parse("{:.arglebargle$}", &[Variant::Int(42)], &NoNamedArguments)
+        (0, ParseErrorKind::MissingNamedArgument { name: "arglebargle".to_string() }),
+        parse_err("{:.arglebargle$}", &[Variant::Int(42)], &NoNamedArguments)
     );
 }
 
 #[test]
 fn missing_asterisk_precision() {
     assert_eq!(
-        Err(3),
-        parse("{} {0:.*}", &[Variant::Int(42)], &NoNamedArguments)
+        (3, ParseErrorKind::ArgumentIndexOutOfRange { index: 1, available: 1 }),
+        parse_err("{} {0:.*}", &[Variant::Int(42)], &NoNamedArguments)
+    );
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn unsupported_format() {
+    assert_eq!(
+        (
+            4,
+            ParseErrorKind::UnsupportedFormat {
+                argument: Some(ArgumentRef::Next),
+                specifier: Specifier { format: Format::LowerHex, ..Default::default() },
+            }
+        ),
+        parse_err("foo {:x}", &[Variant::Float(42.042)], &NoNamedArguments)
+    );
+
+    let mut named = HashMap::new();
+    named.insert("foo", Variant::Float(42.042));
+    let err = parse("{foo:>8x}", &NoPositionalArguments, &named).unwrap_err();
+    assert_eq!(
+        "argument `foo` does not support the format `:>8x` at offset 0",
+        err.to_string()
     );
+    let err = parse("{1:#b}", &[Variant::Int(1), Variant::Float(0.5)], &named).unwrap_err();
+    assert_eq!("argument 1 does not support the format `:#b` at offset 0", err.to_string());
 }
 
+#[test]
+fn invalid_size_argument() {
+    assert_eq!(
+        (0, ParseErrorKind::InvalidSizeArgument),
+        parse_err("{:1$}", &[Variant::Int(42), Variant::Float(5.0)], &NoNamedArguments)
+    );
+}
+
+#[test]
+fn parse_error_display() {
+    let err = parse("foo {", &NoPositionalArguments, &NoNamedArguments).unwrap_err();
+    assert_eq!("unmatched brace at offset 4", err.to_string());
+
+    let args = [Variant::Int(42), Variant::Int(5)];
+    let err = parse("foo {2}", &args, &NoNamedArguments).unwrap_err();
+    assert_eq!("argument 2 requested, but only 2 provided at offset 4", err.to_string());
+
+    let err = parse("{bar}", &NoPositionalArguments, &NoNamedArguments).unwrap_err();
+    assert_eq!("missing named argument `bar` at offset 0", err.to_string());
+}
+
+#[cfg(feature = "std")]
 #[test]
 fn named_argument_validity() {
     let mut map = HashMap::new();
     map.insert("ascii_identifier".to_string(), Variant::Int(42));
     map.insert("_leading_underscore".to_string(), Variant::Int(4242));
     map.insert("уникод".to_string(), Variant::Float(42.042));
+    map.insert("user_name".to_string(), Variant::Str("foo".to_string()));
+    map.insert("名前".to_string(), Variant::Int(8));
+    map.insert("_width".to_string(), Variant::Int(6));
     map.insert("0leading_digit".to_string(), Variant::Int(-42));
     map.insert("invalid/character".to_string(), Variant::Float(-42.042));
 
     assert!(parse("{ascii_identifier}", &NoPositionalArguments, &map).is_ok());
     assert!(parse("{_leading_underscore}", &NoPositionalArguments, &map).is_ok());
     assert!(parse("{уникод}", &NoPositionalArguments, &map).is_ok());
+    assert!(parse("{user_name}", &NoPositionalArguments, &map).is_ok());
+    assert!(parse("{名前}", &NoPositionalArguments, &map).is_ok());
+    assert!(parse("{user_name:_width$}", &NoPositionalArguments, &map).is_ok());
+    assert!(parse("{уникод:名前$.名前$}", &NoPositionalArguments, &map).is_ok());
 
-    assert_eq!(Err(0), parse("{0leading_digit}", &NoPositionalArguments, &map));
-    assert_eq!(Err(0), parse("{invalid/character}", &NoPositionalArguments, &map));
+    assert_eq!(
+        (0, ParseErrorKind::InvalidSpecifier),
+        parse_err("{0leading_digit}", &NoPositionalArguments, &map)
+    );
+    assert_eq!(
+        (0, ParseErrorKind::InvalidSpecifier),
+        parse_err("{invalid/character}", &NoPositionalArguments, &map)
+    );
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn dotted_argument_names() {
+    let mut map = HashMap::new();
+    map.insert("user".to_string(), Variant::Int(42));
+
+    assert!(parse("{user}", &NoPositionalArguments, &map).is_ok());
+    assert_eq!(
+        (0, ParseErrorKind::MissingNamedArgument { name: "user.name".to_string() }),
+        parse_err("{user.name}", &NoPositionalArguments, &map)
+    );
+    assert_eq!(
+        (0, ParseErrorKind::InvalidSpecifier),
+        parse_err("{user.}", &NoPositionalArguments, &map)
+    );
+    assert_eq!(
+        (0, ParseErrorKind::InvalidSpecifier),
+        parse_err("{user..name}", &NoPositionalArguments, &map)
+    );
 }
 
 #[test]
 fn parse_specifier_smoke_test() {
+    use rt_format::Measure;
+
     struct NoValues;
     impl ArgumentSource<Variant> for NoValues {
         fn next_argument(&mut self) -> Option<&Variant> { None }
@@ -169,14 +283,1128 @@ fn parse_specifier_smoke_test() {
 
     assert_eq!(
         Ok(Specifier {
+            fill: None,
             align: Align::Right,
             sign: Sign::Always,
             repr: Repr::Alt,
             pad: Pad::Zero,
             width: Width::AtLeast { width: 42 },
+            grouping: Grouping::None,
+            max_width: MaxWidth::Unlimited,
+            locale: Locale::C,
+            measure: Measure::Chars,
             precision: Precision::Exactly { precision: 17 },
             format: Format::UpperExp,
         }),
         parse_specifier(">+#042.17E", &mut NoValues {})
     );
 }
+
+#[test]
+fn fill_with_zero_pad() {
+    // Like in `format!`, zero padding overrides the fill and the alignment of a number.
+    let args = [Variant::Int(-42), Variant::Float(1.5), Variant::Str("ab".to_string())];
+    assert_eq!(
+        format!("{:*<08} {:~^+09.2} {:*>08} {:_<05}", -42, 1.5, "ab", "ab"),
+        parse("{:*<08} {:~^+09.2} {2:*>08} {2:_<05}", &args, &NoNamedArguments)
+            .unwrap()
+            .to_string()
+    );
+}
+
+#[test]
+fn parse_specifier_fill() {
+    struct NoValues;
+    impl ArgumentSource<Variant> for NoValues {
+        fn next_argument(&mut self) -> Option<&Variant> { None }
+        fn lookup_argument_by_index(&self, _: usize) -> Option<&Variant> { None }
+        fn lookup_argument_by_name(&self, _: &str) -> Option<&Variant> { None }
+    }
+
+    assert_eq!(
+        Ok(Specifier {
+            fill: Some('→'),
+            align: Align::Center,
+            width: Width::AtLeast { width: 8 },
+            ..Default::default()
+        }),
+        parse_specifier("→^8", &mut NoValues {})
+    );
+}
+
+#[test]
+fn parse_specifier_trailing_text() {
+    struct NoValues;
+    impl ArgumentSource<Variant> for NoValues {
+        fn next_argument(&mut self) -> Option<&Variant> { None }
+        fn lookup_argument_by_index(&self, _: usize) -> Option<&Variant> { None }
+        fn lookup_argument_by_name(&self, _: &str) -> Option<&Variant> { None }
+    }
+
+    assert_eq!(
+        Err(ParseError::new(0, ParseErrorKind::InvalidSpecifier)),
+        parse_specifier("8x}", &mut NoValues {})
+    );
+}
+
+#[test]
+fn parse_specifier_without_arguments() {
+    assert_eq!(
+        Ok(Specifier {
+            align: Align::Left,
+            width: Width::AtLeast { width: 8 },
+            precision: Precision::Exactly { precision: 2 },
+            format: Format::LowerExp,
+            ..Default::default()
+        }),
+        parse_specifier_str("<8.2e")
+    );
+    assert_eq!(
+        Err(ParseError::new(0, ParseErrorKind::InvalidSpecifier)),
+        parse_specifier_str("8x}")
+    );
+
+    let requires =
+        |argument| Err(ParseError::new(0, ParseErrorKind::RequiresArgument { argument }));
+    assert_eq!(requires(ArgumentRef::Positional(1)), parse_specifier_str(">1$"));
+    assert_eq!(requires(ArgumentRef::Next), parse_specifier_str(".*"));
+    assert_eq!(requires(ArgumentRef::Named("w".to_string())), parse_specifier_str("w$.2"));
+    assert_eq!(
+        "width or precision requires argument `w` at offset 0",
+        parse_specifier_str("w$").unwrap_err().to_string()
+    );
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn template_bind_many() {
+    let template = Template::parse("{} {:.*} {foo:>1$}").unwrap();
+
+    let mut map = HashMap::new();
+    map.insert("foo", Variant::Int(42));
+    assert_eq!(
+        "1 5.000  42",
+        template
+            .bind(&[Variant::Int(1), Variant::Int(3), Variant::Float(5.0)], &map)
+            .unwrap()
+            .to_string()
+    );
+
+    map.insert("foo", Variant::Int(-1));
+    assert_eq!(
+        "2 0.5 -1",
+        template
+            .bind(&[Variant::Int(2), Variant::Int(1), Variant::Float(0.5)], &map)
+            .unwrap()
+            .to_string()
+    );
+}
+
+#[test]
+fn template_syntax_error() {
+    let err = Template::parse("foo {:%}").unwrap_err();
+    assert_eq!((4, &ParseErrorKind::InvalidSpecifier), (err.offset(), err.kind()));
+}
+
+#[test]
+fn template_bind_error() {
+    let template = Template::parse("foo {1}").unwrap();
+    let err = template.bind(&[Variant::Int(42)], &NoNamedArguments).unwrap_err();
+    assert_eq!(
+        (4, &ParseErrorKind::ArgumentIndexOutOfRange { index: 1, available: 1 }),
+        (err.offset(), err.kind())
+    );
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn substitution_argument_ref() {
+    let mut map = HashMap::new();
+    map.insert("foo", Variant::Int(42));
+    let parsed = parse("{} {0:.*} {foo:1$}", &[Variant::Int(1), Variant::Int(3)], &map).unwrap();
+    let refs: Vec<&ArgumentRef> = parsed
+        .segments()
+        .iter()
+        .filter_map(|segment| match segment {
+            Segment::Substitution(sub) => sub.argument_ref(),
+            Segment::Text { .. } => None,
+        })
+        .collect();
+    assert_eq!(
+        vec![
+            &ArgumentRef::Next,
+            &ArgumentRef::Positional(0),
+            &ArgumentRef::Named("foo".to_string()),
+        ],
+        refs
+    );
+}
+
+#[test]
+fn specifier_from_str() {
+    assert_eq!(
+        Ok(Specifier {
+            fill: Some('*'),
+            align: Align::Center,
+            repr: Repr::Alt,
+            width: Width::AtLeast { width: 10 },
+            precision: Precision::Exactly { precision: 2 },
+            format: Format::LowerExp,
+            ..Default::default()
+        }),
+        "*^#10.2e".parse::<Specifier>()
+    );
+    assert_eq!(Ok(Specifier::default()), "".parse::<Specifier>());
+    assert_eq!(
+        Err(ParseError::new(0, ParseErrorKind::InvalidSpecifier)),
+        "8x}".parse::<Specifier>()
+    );
+    assert_eq!(
+        Err(ParseError::new(
+            0,
+            ParseErrorKind::RequiresArgument { argument: ArgumentRef::Positional(1) }
+        )),
+        "1$".parse::<Specifier>()
+    );
+    assert_eq!(
+        Err(ParseError::new(
+            0,
+            ParseErrorKind::RequiresArgument { argument: ArgumentRef::Named("prec".to_string()) }
+        )),
+        ".prec$".parse::<Specifier>()
+    );
+}
+
+/// Returns a copy of each of the specifiers for each of the values, set with the given function.
+fn vary<T: Copy>(
+    specs: Vec<Specifier>,
+    values: &[T],
+    set: fn(&mut Specifier, T),
+) -> Vec<Specifier> {
+    specs
+        .iter()
+        .flat_map(|spec| {
+            values.iter().map(move |value| {
+                let mut spec = *spec;
+                set(&mut spec, *value);
+                spec
+            })
+        })
+        .collect()
+}
+
+#[test]
+fn specifier_round_trip() {
+    let specs = vec![Specifier::default()];
+    let specs = vary(specs, &[None, Some('*'), Some('0'), Some('<')], |s, v| s.fill = v);
+    let specs = vary(specs, &[Align::None, Align::Left, Align::Center, Align::Right], |s, v| {
+        s.align = v
+    });
+    let specs = vary(specs, &[Sign::Default, Sign::Always], |s, v| s.sign = v);
+    let specs = vary(specs, &[Repr::Default, Repr::Alt], |s, v| s.repr = v);
+    let specs = vary(specs, &[Pad::Space, Pad::Zero], |s, v| s.pad = v);
+    let specs = vary(specs, &[None, Some(0), Some(1), Some(12)], |s, v| {
+        s.width = v.map_or(Width::Auto, |width| Width::AtLeast { width })
+    });
+    let specs = vary(specs, &[Grouping::None, Grouping::Comma, Grouping::Space], |s, v| {
+        s.grouping = v
+    });
+    let specs = vary(
+        specs,
+        &[MaxWidth::Unlimited, MaxWidth::Cut { width: 0 }, MaxWidth::Ellipsis { width: 5 }],
+        |s, v| s.max_width = v,
+    );
+    let specs = vary(specs, &[None, Some(0), Some(3)], |s, v| {
+        s.precision = v.map_or(Precision::Auto, |precision| Precision::Exactly { precision })
+    });
+    let specs = vary(
+        specs,
+        &[Format::Display, Format::Debug, Format::UpperExp, Format::Custom('t')],
+        |s, v| s.format = v,
+    );
+
+    for spec in specs {
+        // A fill is only written with an alignment.
+        let fill_lost = spec.fill.is_some() && spec.align == Align::None;
+        // A width of zero without zero padding is written as `0`, which reads as zero padding.
+        let zero_width = spec.width == Width::AtLeast { width: 0 } && spec.pad == Pad::Space;
+        if !fill_lost && !zero_width {
+            assert_eq!(Ok(spec), spec.to_string().parse::<Specifier>(), "{}", spec);
+        }
+    }
+}
+
+#[test]
+fn specifier_round_trip_exceptions() {
+    let spec = Specifier {
+        fill: Some('*'),
+        width: Width::AtLeast { width: 8 },
+        ..Default::default()
+    };
+    assert_eq!("8", spec.to_string());
+    let spec = Specifier { fill: Some('*'), align: Align::Left, pad: Pad::Zero, ..spec };
+    assert_eq!(Ok(spec), spec.to_string().parse::<Specifier>());
+    let spec = Specifier { width: Width::AtLeast { width: 0 }, ..Default::default() };
+    assert_eq!(
+        Ok(Specifier { pad: Pad::Zero, ..Default::default() }),
+        spec.to_string().parse::<Specifier>()
+    );
+    // A standard letter is always read as the standard format.
+    let spec = Specifier { format: Format::Custom('x'), ..Default::default() };
+    assert_eq!(Ok(Format::LowerHex), spec.to_string().parse::<Specifier>().map(|s| s.format));
+    // The width and precision can't refer to arguments, so `Display` never writes them that way.
+    assert!("1$".parse::<Specifier>().is_err());
+    assert!(".*".parse::<Specifier>().is_err());
+}
+
+#[test]
+fn specifier_part_orderings() {
+    // Builds every ordering of every subset of the parts, and checks that the ones in the
+    // canonical order are accepted, and that every accepted one is written back the same way,
+    // even if it means something else, like `+^`, which is the fill `+` with centering.
+    const PARTS: &[&str] = &["^", "+", "#", "0", "7", ".2"];
+    fn orderings(used: &mut Vec<usize>, all: &mut Vec<Vec<usize>>) {
+        all.push(used.clone());
+        for idx in 0..PARTS.len() {
+            if !used.contains(&idx) {
+                used.push(idx);
+                orderings(used, all);
+                used.pop();
+            }
+        }
+    }
+    let mut all = Vec::new();
+    orderings(&mut Vec::new(), &mut all);
+
+    for order in all {
+        let spec_str: String = order.iter().map(|&idx| PARTS[idx]).collect();
+        match spec_str.parse::<Specifier>() {
+            Ok(spec) => assert_eq!(spec_str, spec.to_string()),
+            Err(_) => assert!(order.windows(2).any(|pair| pair[0] > pair[1]), "{}", spec_str),
+        }
+    }
+    assert!("#+".parse::<Specifier>().is_err());
+    assert!("0#".parse::<Specifier>().is_err());
+    assert!("+^5".parse::<Specifier>().is_ok_and(|spec| spec.fill == Some('+')));
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn specifier_placeholder_for() {
+    let foo = ArgumentRef::Named("foo".to_string());
+    assert_eq!("{foo}", Specifier::default().placeholder_for(&foo, None, None));
+    assert_eq!("{}", Specifier::default().placeholder_for(&ArgumentRef::Next, None, None));
+
+    let spec: Specifier = "*^+#12,~20.3e".parse().unwrap();
+    assert_eq!("{foo:*^+#12,~20.3e}", spec.placeholder_for(&foo, None, None));
+    assert_eq!("{3:*^+#12,~20.3e}", spec.placeholder_for(&ArgumentRef::Positional(3), None, None));
+    let hex = "#x".parse::<Specifier>().unwrap();
+    assert_eq!("{:#x}", hex.placeholder_for(&ArgumentRef::Next, None, None));
+    let width = ArgumentRef::Positional(1);
+    assert_eq!("{:#1$x}", hex.placeholder_for(&ArgumentRef::Next, Some(&width), None));
+    assert_eq!(
+        "{foo:*^+#foo$,~20.*e}",
+        spec.placeholder_for(&foo, Some(&foo), Some(&ArgumentRef::Next))
+    );
+
+    let mut named = HashMap::new();
+    named.insert("foo", Variant::Float(1.5));
+    let placeholder = spec.placeholder_for(&foo, None, None);
+    let parsed = parse(&placeholder, &NoPositionalArguments, &named).unwrap();
+    match parsed.segments() {
+        [Segment::Substitution(substitution)] => assert_eq!(&spec, substitution.specifier()),
+        segments => panic!("unexpected segments: {:?}", segments),
+    }
+}
+
+#[test]
+fn custom_format_tag() {
+    assert_eq!(
+        Ok(Specifier {
+            width: Width::AtLeast { width: 8 },
+            format: Format::Custom('t'),
+            ..Default::default()
+        }),
+        "8t".parse::<Specifier>()
+    );
+    assert_eq!(">8t", ">8t".parse::<Specifier>().unwrap().to_string());
+    assert_eq!(
+        Err(ParseError::new(0, ParseErrorKind::InvalidSpecifier)),
+        "tt".parse::<Specifier>()
+    );
+    assert!(matches!(
+        parse_err("{:t}", &[Variant::Int(42)], &NoNamedArguments),
+        (0, ParseErrorKind::UnsupportedFormat { .. })
+    ));
+}
+
+#[test]
+fn template_visit() {
+    use rt_format::TemplateVisitor;
+
+    #[derive(Default)]
+    struct Recorder(Vec<String>);
+
+    impl TemplateVisitor for Recorder {
+        fn literal(&mut self, text: &str) {
+            self.0.push(format!("text {:?}", text));
+        }
+
+        fn placeholder(&mut self, arg: &ArgumentRef, spec: &Specifier) {
+            self.0.push(format!("{:?} {:?}", arg, spec.to_string()));
+        }
+    }
+
+    let template = Template::parse("{{{foo:>8x}}} {} {1:.*}").unwrap();
+    let mut recorder = Recorder::default();
+    template.visit(&mut recorder);
+    assert_eq!(
+        vec![
+            r#"text "{""#,
+            r#"Named("foo") ">8x""#,
+            r#"text "}""#,
+            r#"text " ""#,
+            r#"Next """#,
+            r#"text " ""#,
+            r#"Positional(1) """#,
+        ],
+        recorder.0
+    );
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn template_map_placeholders() {
+    let mut template = Template::parse("{price:>8.2} {:w$.p$} {0:.*} {{{price}}}").unwrap();
+    let mut seen = Vec::new();
+    template.map_placeholders(|arg, spec| {
+        seen.push(format!("{:?} {:?}", arg, spec.to_string()));
+        match arg {
+            ArgumentRef::Named(name) if name == "price" => *name = "total".to_string(),
+            ArgumentRef::Next => spec.width = Width::AtLeast { width: 5 },
+            ArgumentRef::Positional(_) => spec.precision = Precision::Exactly { precision: 1 },
+            _ => (),
+        }
+    });
+    assert_eq!(
+        vec![
+            r#"Named("price") ">8.2""#,
+            r#"Next """#,
+            r#"Positional(0) """#,
+            r#"Named("price") """#,
+        ],
+        seen
+    );
+    assert_eq!(vec!["total", "p"], template.required_names().collect::<Vec<_>>());
+
+    let positional = [Variant::Float(1.3)];
+    let named: HashMap<&str, Variant> =
+        vec![("total", Variant::Float(2.5)), ("p", Variant::Int(3))].into_iter().collect();
+    let parsed = template.bind(&positional, &named).unwrap();
+    assert_eq!("    2.50 1.300 1.3 {2.5}", parsed.to_string());
+}
+
+#[test]
+fn template_to_format_string() {
+    let formats = [
+        "",
+        "plain {{text}} with }}{{ braces",
+        "{} {:?} {0} {1:x} {name}",
+        "{:*^+#12_~20.3e}",
+        "{0:>w$.p$} {:1$} {:.*} {:.2$}",
+        "{name?fallback} {other?n/a:>6}",
+        "{user.name:<10} {:08.3} {:…5}",
+    ];
+    for format in formats.iter() {
+        let template = Template::parse(format).unwrap();
+        let written = template.to_format_string();
+        assert_eq!(*format, written);
+        let reparsed = Template::parse(&written).unwrap();
+        assert_eq!(written, reparsed.to_format_string());
+    }
+
+    assert_eq!("{:>03} {0}", Template::parse("{:>03} {0:}").unwrap().to_format_string());
+    let trimmed = SyntaxOptions { trimmed_names: true, ..SyntaxOptions::default() };
+    assert_eq!(
+        "{a} {b:x}",
+        Template::parse_with("{ a } { b : x }", trimmed).unwrap().to_format_string()
+    );
+
+    let mut template = Template::parse("{{{price:>w$.2}}}").unwrap();
+    template.map_placeholders(|arg, spec| {
+        *arg = ArgumentRef::Named("total".to_string());
+        spec.width = Width::AtLeast { width: 8 };
+    });
+    assert_eq!("{{{total:>8.2}}}", template.to_format_string());
+}
+
+#[cfg(feature = "index-or-name")]
+#[test]
+fn index_or_name() {
+    let named = [("legacy", Variant::Int(7))];
+    let present = [Variant::Int(42)];
+    let parsed = parse("{0|legacy} [{0|legacy:>4}]", &present, &named).unwrap();
+    assert_eq!("42 [  42]", parsed.to_string());
+
+    let absent: [Variant; 0] = [];
+    let parsed = parse("{0|legacy} [{0|legacy:<4}]", &absent, &named).unwrap();
+    assert_eq!("7 [7   ]", parsed.to_string());
+    match &parsed.segments()[0] {
+        Segment::Substitution(substitution) => assert_eq!(
+            Some(&ArgumentRef::Named("legacy".to_string())),
+            substitution.argument_ref()
+        ),
+        segment => panic!("unexpected segment: {:?}", segment),
+    }
+
+    assert_eq!(
+        (0, ParseErrorKind::ArgumentIndexOutOfRange { index: 0, available: 0 }),
+        parse_err("{0|other}", &absent, &named)
+    );
+    assert_eq!("none", parse("{0|other?none}", &absent, &named).unwrap().to_string());
+    assert_eq!((0, ParseErrorKind::InvalidSpecifier), parse_err("{0|}", &present, &named));
+    assert!(strict("{0|legacy}", &absent, &named).is_ok());
+
+    let template = Template::parse("{{{1|a.b:x}}}").unwrap();
+    assert_eq!("{{{1|a.b:x}}}", template.to_format_string());
+}
+
+#[test]
+fn parse_grouping() {
+    assert_eq!(
+        Ok(Specifier {
+            width: Width::AtLeast { width: 8 },
+            grouping: Grouping::Comma,
+            precision: Precision::Exactly { precision: 2 },
+            ..Default::default()
+        }),
+        "8,.2".parse::<Specifier>()
+    );
+    assert_eq!(
+        Ok(Specifier {
+            grouping: Grouping::Underscore,
+            format: Format::LowerHex,
+            ..Default::default()
+        }),
+        "_x".parse::<Specifier>()
+    );
+    assert_eq!(
+        Ok(Specifier { fill: Some(','), align: Align::Right, ..Default::default() }),
+        ",>".parse::<Specifier>()
+    );
+    assert_eq!("^+08 .3", "^+08 .3".parse::<Specifier>().unwrap().to_string());
+}
+
+#[test]
+fn parse_max_width() {
+    assert_eq!(
+        Ok(Specifier {
+            width: Width::AtLeast { width: 8 },
+            max_width: MaxWidth::Cut { width: 12 },
+            precision: Precision::Exactly { precision: 2 },
+            ..Default::default()
+        }),
+        "8~12.2".parse::<Specifier>()
+    );
+    assert_eq!(
+        Ok(Specifier { max_width: MaxWidth::Ellipsis { width: 5 }, ..Default::default() }),
+        "…5".parse::<Specifier>()
+    );
+    assert_eq!(
+        Err(ParseError::new(0, ParseErrorKind::InvalidSpecifier)),
+        "~".parse::<Specifier>()
+    );
+    assert_eq!(">10,…8.3e", ">10,…8.3e".parse::<Specifier>().unwrap().to_string());
+}
+
+#[test]
+fn parsed_format_into_iter() {
+    let args = [Variant::Int(42)];
+    let parsed = parse("foo {} bar", &args, &NoNamedArguments).unwrap();
+    assert_eq!(3, parsed.segments().len());
+    assert_eq!(
+        vec!["foo ", "42", " bar"],
+        (&parsed).into_iter().map(|segment| segment.to_string()).collect::<Vec<_>>()
+    );
+
+    let segments: Vec<Segment<Variant>> = parsed.into_iter().collect();
+    assert!(matches!(segments[0], Segment::Text { text: "foo ", .. }));
+    assert!(matches!(
+        &segments[1],
+        Segment::Substitution(sub) if std::ptr::eq(sub.value(), &args[0])
+    ));
+}
+
+#[test]
+fn unmatched_brace_next_to_substitution() {
+    let args = [Variant::Int(42)];
+    assert_eq!((2, ParseErrorKind::UnmatchedBrace), parse_err("{}}", &args, &NoNamedArguments));
+    assert_eq!((4, ParseErrorKind::UnmatchedBrace), parse_err("}}{}{", &args, &NoNamedArguments));
+    assert_eq!((3, ParseErrorKind::UnmatchedBrace), parse_err("a}}}b", &args, &NoNamedArguments));
+}
+
+#[test]
+fn template_parse_all() {
+    assert!(Template::parse_all("{} {foo:>8} {{}}").is_ok());
+    assert_eq!(
+        vec![
+            ParseError::with_span(0..4, ParseErrorKind::InvalidSpecifier),
+            ParseError::with_span(5..6, ParseErrorKind::UnmatchedBrace),
+            ParseError::with_span(7..13, ParseErrorKind::InvalidSpecifier),
+            ParseError::with_span(19..20, ParseErrorKind::UnmatchedBrace),
+        ],
+        Template::parse_all("{:%} } {0bar} {:x} {").unwrap_err()
+    );
+}
+
+#[test]
+fn template_required_arguments() {
+    let template = Template::parse("{foo} {} {:>width$.*} {2} {foo:.prec$} {{bar}}").unwrap();
+    assert_eq!(vec!["foo", "width", "prec"], template.required_names().collect::<Vec<_>>());
+    assert_eq!(3, template.positional_count_used());
+    assert_eq!(Some(2), template.max_positional_index());
+
+    let template = Template::parse("{} {} {0}").unwrap();
+    assert_eq!(2, template.positional_count_used());
+    assert_eq!(0, template.required_names().count());
+
+    let template = Template::parse("{foo} text").unwrap();
+    assert_eq!(0, template.positional_count_used());
+    assert_eq!(None, template.max_positional_index());
+}
+
+#[test]
+fn parse_strict() {
+    let args = [Variant::Int(1), Variant::Int(2), Variant::Int(3)];
+    let named = [("foo", Variant::Int(4)), ("bar", Variant::Int(5)), ("width", Variant::Int(6))];
+
+    let parsed = strict("{} {2:1$} {foo} {bar:width$}", &args, &named).unwrap();
+    assert_eq!("1  3 4      5", parsed.to_string());
+
+    let err = strict("{1} {foo}", &args, &named).unwrap_err();
+    assert_eq!(
+        (
+            9,
+            &ParseErrorKind::UnusedArguments {
+                positional: vec![0, 2],
+                named: vec!["bar".to_string(), "width".to_string()],
+            }
+        ),
+        (err.offset(), err.kind())
+    );
+    assert_eq!("unused arguments: 0, 2, `bar`, `width` at offset 9", err.to_string());
+
+    assert!(ParsedFormat::parse("{1} {foo}", &args, &named).is_ok());
+    let err = strict("{", &args, &named).unwrap_err();
+    assert_eq!((0, &ParseErrorKind::UnmatchedBrace), (err.offset(), err.kind()));
+
+    // Arguments that can't tell how many there are aren't checked, nor read any further.
+    let calls = std::cell::Cell::new(0);
+    let mut iter = args.iter();
+    let next = NextArguments::new(|| {
+        calls.set(calls.get() + 1);
+        iter.next()
+    });
+    let named = FnNamedArguments::new(|name: &str| match name {
+        "foo" => Some(&args[0]),
+        _ => None,
+    });
+    assert!(strict("{}", &next, &named).is_ok());
+    assert_eq!(1, calls.get());
+}
+
+#[test]
+fn partial_specifier() {
+    use rt_format::PartialSpecifier;
+
+    assert_eq!(Ok(PartialSpecifier::default()), "".parse::<PartialSpecifier>());
+    assert_eq!(
+        Ok(PartialSpecifier {
+            precision: Some(Precision::Exactly { precision: 0 }),
+            ..Default::default()
+        }),
+        ".0".parse::<PartialSpecifier>()
+    );
+    assert_eq!(
+        Err(ParseError::new(
+            0,
+            ParseErrorKind::RequiresArgument { argument: ArgumentRef::Positional(1) }
+        )),
+        "1$".parse::<PartialSpecifier>()
+    );
+
+    let base: Specifier = "*^+#10,.2x".parse().unwrap();
+    assert_eq!("*<+#10,.2x", base.merge("<").unwrap().to_string());
+    assert_eq!("-<+#4,.2X", base.merge("-<4X").unwrap().to_string());
+    assert_eq!("*^+#10,.0x", base.merge(".0").unwrap().to_string());
+    assert_eq!(base, base.merge("").unwrap());
+    assert_eq!(
+        Err(ParseError::new(0, ParseErrorKind::InvalidSpecifier)),
+        base.merge("<<<")
+    );
+}
+
+#[test]
+fn parse_error_span() {
+    let args = [Variant::Int(42)];
+    let span = |format| parse(format, &args, &NoNamedArguments).unwrap_err().span();
+    assert_eq!(3..4, span("ok {bad"));
+    assert_eq!(0..4, span("{:%}"));
+    assert_eq!(3..9, span("ok {:%.x} {}"));
+    assert_eq!(2..3, span("{}}"));
+    assert_eq!(3..5, span("{} {}"));
+    assert_eq!(0..7, span("{foo:x} {}"));
+    assert_eq!(0..4, span("{:t}"));
+}
+
+#[test]
+fn parse_error_line_col() {
+    let args = [Variant::Int(42)];
+    let line_col = |format| parse(format, &args, &NoNamedArguments).unwrap_err().line_col(format);
+    assert_eq!((1, 3), line_col("ok {bad"));
+    assert_eq!((3, 2), line_col("a\nb\nc {:%}"));
+    assert_eq!((3, 0), line_col("a\r\n\r\n{1}"));
+    assert_eq!((4, 1), line_col("a\r\rb\n\t{:%}"));
+    assert_eq!((2, 2), line_col("漢字\n😀é{"));
+}
+
+#[test]
+fn dimension_display() {
+    assert_eq!("", Align::None.to_string());
+    assert_eq!("^", Align::Center.to_string());
+    assert_eq!("+", Sign::Always.to_string());
+    assert_eq!("#", Repr::Alt.to_string());
+    assert_eq!("0", Pad::Zero.to_string());
+    assert_eq!("", Width::Auto.to_string());
+    assert_eq!("12", Width::AtLeast { width: 12 }.to_string());
+    assert_eq!(".3", Precision::Exactly { precision: 3 }.to_string());
+    assert_eq!("X", Format::UpperHex.to_string());
+    assert_eq!("?", Format::Debug.to_string());
+    assert_eq!("_", Grouping::Underscore.to_string());
+    assert_eq!("…8", MaxWidth::Ellipsis { width: 8 }.to_string());
+
+    let spec: Specifier = "*>+#12_…8.3X".parse().unwrap();
+    let parts = [
+        spec.align.to_string(),
+        spec.sign.to_string(),
+        spec.repr.to_string(),
+        spec.pad.to_string(),
+        spec.width.to_string(),
+        spec.grouping.to_string(),
+        spec.max_width.to_string(),
+        spec.precision.to_string(),
+        spec.format.to_string(),
+    ];
+    assert_eq!(spec.to_string(), format!("*{}", parts.concat()));
+}
+
+#[test]
+fn width_overflow() {
+    let args = [Variant::Int(42), Variant::Int(5000)];
+    assert_eq!(
+        (0, ParseErrorKind::WidthOverflow),
+        parse_err("{:99999999999999999999999}", &args, &NoNamedArguments)
+    );
+    assert_eq!(
+        (0, ParseErrorKind::WidthOverflow),
+        parse_err("{:.99999999999999999999999}", &args, &NoNamedArguments)
+    );
+    assert_eq!(5000, parse("{:5000}", &args, &NoNamedArguments).unwrap().to_string().len());
+    assert_eq!(5000, parse("{:1$}", &args, &NoNamedArguments).unwrap().to_string().len());
+    assert!("5000".parse::<Specifier>().is_ok());
+
+    let bind = |format, max_size| {
+        let limits = FormatLimits { max_width: max_size, max_precision: max_size };
+        let template = Template::parse(format).unwrap().with_limits(limits);
+        template.bind(&args, &NoNamedArguments).map_err(|err| (err.offset(), err.kind().clone()))
+    };
+    let exceeded = ParseErrorKind::LimitExceeded { size: 5000, limit: 4096 };
+    assert_eq!(Err((0, exceeded.clone())), bind("{:5000}", 4096));
+    assert_eq!(Err((3, exceeded.clone())), bind("{} {:1$}", 4096));
+    assert_eq!(Err((0, exceeded.clone())), bind("{:.1$}", 4096));
+    assert!(bind("{:4096}", 4096).is_ok());
+    assert_eq!(5000, bind("{:1$}", 5000).unwrap().to_string().len());
+    let template = Template::parse("{:4097}").unwrap().with_limits(FormatLimits::UNTRUSTED);
+    assert!(template.bind(&args, &NoNamedArguments).is_err());
+}
+
+#[test]
+fn format_limits() {
+    let args = [Variant::Float(1.5), Variant::Int(20)];
+    let limits = FormatLimits { max_width: 10, max_precision: 3 };
+    let parse = |format| {
+        ParsedFormat::from_parser(Parser::new(format, &args, &NoNamedArguments).with_limits(limits))
+    };
+    assert_eq!("  1.500", parse("{:7.3}").unwrap().to_string());
+    assert_eq!(
+        &ParseErrorKind::LimitExceeded { size: 11, limit: 10 },
+        parse("{:11}").unwrap_err().kind()
+    );
+    assert_eq!(
+        &ParseErrorKind::LimitExceeded { size: 20, limit: 10 },
+        parse("{:1$}").unwrap_err().kind()
+    );
+    assert_eq!(
+        &ParseErrorKind::LimitExceeded { size: 4, limit: 3 },
+        parse("{:.4}").unwrap_err().kind()
+    );
+    assert_eq!(
+        "width or precision 4 exceeds the limit of 3 at offset 0",
+        parse("{:.4}").unwrap_err().to_string()
+    );
+
+    let unlimited = FormatLimits::UNLIMITED;
+    let parser = Parser::new("{:5000}", &args, &NoNamedArguments).with_limits(unlimited);
+    let parsed = ParsedFormat::from_parser(parser);
+    assert_eq!(5000, parsed.unwrap().to_string().len());
+    let template = Template::parse("{:.4}").unwrap().with_limits(limits);
+    assert!(template.bind(&args, &NoNamedArguments).is_err());
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn trimmed_names() {
+    let mut named = HashMap::new();
+    named.insert("foo", Variant::Str("ab".to_string()));
+    named.insert("n", Variant::Int(1234));
+    let positional = [Variant::Int(7)];
+    let trimmed = |format| {
+        let parser = Parser::new(format, &positional, &named).with_trimmed_names();
+        ParsedFormat::from_parser(parser).map(|parsed| parsed.to_string())
+    };
+
+    assert_eq!(
+        Ok("[ab] [    ab] [7] [7]".to_string()),
+        trimmed("[{ foo }] [{ foo : >6 }] [{ }] [{\t0\n}]")
+    );
+    assert_eq!(Ok("1,234 0x4d2".to_string()), trimmed("{n :, } {n: #x}"));
+    assert_eq!(Ok("{ foo }".to_string()), trimmed("{{ foo }}"));
+    assert_eq!(
+        Err(ParseError::with_span(0..11, ParseErrorKind::InvalidSpecifier)),
+        trimmed("{foo: > 6 }")
+    );
+    assert!(parse("{ foo }", &positional, &named).is_err());
+    assert!(parse("{foo : >6}", &positional, &named).is_err());
+
+    let trimmed = SyntaxOptions { trimmed_names: true, ..SyntaxOptions::default() };
+    let template = Template::parse_with("{ foo :^6 }", trimmed).unwrap();
+    let parsed = template.bind(&positional, &named).unwrap();
+    assert_eq!("  ab  ", parsed.to_string());
+}
+
+#[test]
+fn counting_argument_source() {
+    struct Values(Vec<Variant>, usize);
+    impl ArgumentSource<Variant> for Values {
+        fn next_argument(&mut self) -> Option<&Variant> {
+            self.1 += 1;
+            self.0.get(self.1 - 1)
+        }
+        fn lookup_argument_by_index(&self, idx: usize) -> Option<&Variant> { self.0.get(idx) }
+        fn lookup_argument_by_name(&self, name: &str) -> Option<&Variant> {
+            match name {
+                "prec" => self.0.last(),
+                _ => None,
+            }
+        }
+    }
+
+    let values = Values(vec![Variant::Int(4), Variant::Int(8), Variant::Int(2)], 0);
+    let mut counting = Counting::new(values);
+    assert_eq!(
+        Ok(Specifier {
+            width: Width::AtLeast { width: 8 },
+            precision: Precision::Exactly { precision: 4 },
+            ..Default::default()
+        }),
+        parse_specifier("1$.*", &mut counting)
+    );
+    assert!(parse_specifier("prec$.prec$", &mut counting).is_ok());
+    assert!(parse_specifier("missing$", &mut counting).is_err());
+
+    assert_eq!(1, counting.count_for_index(0));
+    assert_eq!(1, counting.count_for_index(1));
+    assert_eq!(0, counting.count_for_index(2));
+    assert_eq!(2, counting.count_for_name("prec"));
+    assert_eq!(0, counting.count_for_name("missing"));
+    assert_eq!(vec![(0, 1), (1, 1)], counting.positional_counts().into_iter().collect::<Vec<_>>());
+    assert_eq!(1, counting.named_counts().len());
+    assert_eq!(1, counting.into_inner().1);
+}
+
+#[test]
+fn endless_argument_source() {
+    struct Endless(Variant);
+    impl ArgumentSource<Variant> for Endless {
+        fn next_argument(&mut self) -> Option<&Variant> {
+            None
+        }
+        fn lookup_argument_by_index(&self, _: usize) -> Option<&Variant> {
+            Some(&self.0)
+        }
+        fn lookup_argument_by_name(&self, _: &str) -> Option<&Variant> {
+            None
+        }
+    }
+
+    let mut endless = Endless(Variant::Int(4));
+    assert_eq!(
+        Err(ParseErrorKind::ArgumentIndexOutOfRange {
+            index: 0,
+            available: 0
+        }),
+        parse_specifier(".*", &mut endless).map_err(|err| err.kind().clone())
+    );
+    assert!(parse_specifier("5$", &mut endless).is_ok());
+}
+
+#[test]
+fn multibyte_text_before_braces() {
+    let args = [Variant::Int(42)];
+    let parsed = parse("😀{}\t漢字{0}\n{{é}}", &args, &NoNamedArguments).unwrap();
+    assert_eq!(
+        vec!["😀", "42", "\t漢字", "42", "\n", "{", "é", "}"],
+        parsed.segments().iter().map(|segment| segment.to_string()).collect::<Vec<_>>()
+    );
+    assert!(matches!(parsed.segments()[2], Segment::Text { text: "\t漢字", .. }));
+    let spans: Vec<_> = parsed.segments().iter().map(|segment| segment.span()).collect();
+    assert_eq!(vec![0..4, 4..6, 6..13, 13..16, 16..17, 17..19, 19..21, 21..23], spans);
+
+    assert_eq!((4, ParseErrorKind::UnmatchedBrace), parse_err("😀{", &args, &NoNamedArguments));
+    assert_eq!((6, ParseErrorKind::UnmatchedBrace), parse_err("漢字}", &args, &NoNamedArguments));
+    assert_eq!(
+        (8, ParseErrorKind::ArgumentIndexOutOfRange { index: 1, available: 1 }),
+        parse_err("漢\n😀{1}", &args, &NoNamedArguments)
+    );
+    let span = |format| parse(format, &args, &NoNamedArguments).unwrap_err().span();
+    assert_eq!(8..12, span("字\t😀{:%}"));
+}
+
+#[test]
+fn negative_indices() {
+    let args = [Variant::Int(1), Variant::Int(2), Variant::Int(3)];
+    let parse_neg = |format| {
+        let parser = Parser::new(format, &args, &NoNamedArguments).with_negative_indices();
+        ParsedFormat::from_parser(parser).map(|parsed| parsed.to_string())
+    };
+    assert_eq!(Ok("3 2 1".to_string()), parse_neg("{-1} {-2} {-3}"));
+    assert_eq!(Ok("1 [  3]".to_string()), parse_neg("{} [{-1:>3}]"));
+
+    let err = parse_neg("ab {-4}").unwrap_err();
+    assert_eq!(3..7, err.span());
+    assert_eq!(&ParseErrorKind::NegativeIndexOutOfRange { distance: 4, available: 3 }, err.kind());
+    assert_eq!("argument -4 requested, but only 3 provided at offset 3", err.to_string());
+    let empty: [Variant; 0] = [];
+    let parser = Parser::new("{-1}", &empty, &NoNamedArguments).with_negative_indices();
+    assert_eq!(
+        ParseErrorKind::NegativeIndexOutOfRange { distance: 1, available: 0 },
+        ParsedFormat::from_parser(parser).unwrap_err().kind().clone()
+    );
+    assert_eq!(ParseErrorKind::InvalidSpecifier, parse_neg("{-0}").unwrap_err().kind().clone());
+    assert_eq!(ParseErrorKind::InvalidSpecifier, parse_neg("{-x}").unwrap_err().kind().clone());
+    assert_eq!(ParseErrorKind::InvalidSpecifier, parse_neg("{:-1$}").unwrap_err().kind().clone());
+
+    assert_eq!((0, ParseErrorKind::InvalidSpecifier), parse_err("{-1}", &args, &NoNamedArguments));
+
+    let negative = SyntaxOptions { negative_indices: true, ..SyntaxOptions::default() };
+    let template = Template::parse_with("{-2} {0}", negative).unwrap();
+    assert_eq!(2, template.positional_count_used());
+    assert_eq!("2 1", template.bind(&args, &NoNamedArguments).unwrap().to_string());
+    assert_eq!("{-2:>4}", Specifier {
+        align: Align::Right,
+        width: Width::AtLeast { width: 4 },
+        ..Default::default()
+    }
+    .placeholder_for(&ArgumentRef::FromEnd(2), None, None));
+}
+
+#[test]
+fn template_syntax_options() {
+    use rt_format::OwnedTemplate;
+
+    let args = [Variant::Int(1), Variant::Int(2)];
+    let syntax = SyntaxOptions { trimmed_names: true, negative_indices: true };
+    let template = Template::parse_with("{ -1 :>3 } { 0 }", syntax).unwrap();
+    assert_eq!("  2 1", template.bind(&args, &NoNamedArguments).unwrap().to_string());
+    let owned = OwnedTemplate::parse_with("{ -1 :>3 } { 0 }", syntax).unwrap();
+    assert_eq!("  2 1", owned.bind(&args, &NoNamedArguments).unwrap().to_string());
+    assert!(Template::parse("{ -1 }").is_err());
+
+    let errors = Template::parse_all_with("{ -1 } {-} { 0 } {", syntax).unwrap_err();
+    let spans: Vec<_> = errors.iter().map(ParseError::span).collect();
+    assert_eq!(vec![7..10, 17..18], spans);
+    let template = Template::parse_all_with("{ -1 } { 0 }", syntax).unwrap();
+    assert_eq!("2 1", template.bind(&args, &NoNamedArguments).unwrap().to_string());
+}
+
+#[test]
+#[should_panic(expected = "Parser::with_trimmed_names called on a parser for a template")]
+fn template_parser_syntax_options() {
+    let template = Template::parse("{}").unwrap();
+    let _ = template.parser(&[Variant::Int(1)], &NoNamedArguments).with_trimmed_names();
+}
+
+#[test]
+fn dimension_all_and_symbol() {
+    assert_eq!(&[Align::None, Align::Left, Align::Center, Align::Right], Align::ALL);
+    assert_eq!(&[Sign::Default, Sign::Always], Sign::ALL);
+    assert_eq!(&[Repr::Default, Repr::Alt], Repr::ALL);
+    assert_eq!(&[Pad::Space, Pad::Zero], Pad::ALL);
+    assert_eq!(4, Grouping::ALL.len());
+    assert_eq!(9, Format::ALL.len());
+    assert!(!Format::ALL.iter().any(|format| matches!(format, Format::Custom(_))));
+
+    let symbols: Vec<String> = Format::ALL.iter().map(|f| f.symbol().into_owned()).collect();
+    assert_eq!(vec!["", "?", "o", "x", "X", "b", "e", "E", "p"], symbols);
+    assert_eq!("<", Align::Left.symbol());
+    assert_eq!(" ", Grouping::Space.symbol());
+    assert_eq!("t", Format::Custom('t').symbol());
+    assert_eq!("é", Format::Custom('é').symbol());
+    assert_eq!("é", Format::Custom('é').to_string());
+
+    for format in Format::ALL {
+        assert_eq!(format.to_string(), format.symbol());
+    }
+    for align in Align::ALL {
+        assert_eq!(align.to_string(), align.symbol());
+    }
+    for grouping in Grouping::ALL {
+        assert_eq!(grouping.to_string(), grouping.symbol());
+    }
+}
+
+#[test]
+fn owned_template() {
+    use rt_format::parser::MissingBehavior;
+    use rt_format::OwnedTemplate;
+
+    struct Holder {
+        template: OwnedTemplate,
+    }
+
+    let holder = Holder {
+        template: OwnedTemplate::parse(["é", "{{}}", " {x:>3}"].concat()).unwrap(),
+    };
+    assert_eq!("é{{}} {x:>3}", holder.template.as_str());
+    for value in 1..3 {
+        let named = [("x", Variant::Int(value))];
+        let parsed = holder.template.bind(&NoPositionalArguments, &named).unwrap();
+        assert_eq!(format!("é{{}}   {}", value), parsed.to_string());
+        assert!(matches!(parsed.segments()[0], Segment::Text { text: "é", .. }));
+        let spans: Vec<_> = parsed.segments().iter().map(|segment| segment.span()).collect();
+        assert_eq!(vec![0..2, 2..4, 4..6, 6..7, 7..13], spans);
+    }
+    let no_named: [(&str, Variant); 0] = [];
+    let err = holder.template.bind(&NoPositionalArguments, &no_named).unwrap_err();
+    assert_eq!(7..13, err.span());
+    assert_eq!(ParseErrorKind::MissingNamedArgument { name: "x".to_string() }, *err.kind());
+
+    let err = OwnedTemplate::parse("ab{").unwrap_err();
+    assert_eq!((2, &ParseErrorKind::UnmatchedBrace), (err.offset(), err.kind()));
+
+    let owned = {
+        let format = String::from("{a} {b}");
+        Template::parse(&format)
+            .unwrap()
+            .with_missing_behavior(MissingBehavior::Passthrough)
+            .to_owned_template()
+    };
+    let named = [("a", Variant::Int(1))];
+    assert_eq!("1 {b}", owned.bind(&NoPositionalArguments, &named).unwrap().to_string());
+}
+
+#[test]
+fn template_check_against_signature() {
+    use rt_format::parser::{FormatSignature, SignatureError};
+
+    let signature = FormatSignature::new()
+        .with_argument("price", &[Format::Display, Format::LowerExp])
+        .with_argument("width", &[])
+        .with_argument("user.name", &[Format::Display, Format::Debug]);
+    assert_eq!(Some(&[][..]), signature.formats("width"));
+    assert_eq!(None, signature.formats("user"));
+
+    let check = |format| Template::parse(format).unwrap().check_against(&signature);
+    assert_eq!(Ok(()), check("{price:>width$.2e} {user.name:?}"));
+    assert_eq!(
+        Err(vec![
+            SignatureError::UnknownName { name: "w".to_string(), span: 0..11 },
+            SignatureError::UnsupportedFormat {
+                name: "price".to_string(),
+                format: Format::Debug,
+                span: 0..11,
+            },
+            SignatureError::PositionalArgument { argument: ArgumentRef::Next, span: 12..14 },
+            SignatureError::UnsupportedFormat {
+                name: "width".to_string(),
+                format: Format::Display,
+                span: 15..22,
+            },
+            SignatureError::PositionalArgument {
+                argument: ArgumentRef::Positional(0),
+                span: 23..28,
+            },
+            SignatureError::PositionalArgument { argument: ArgumentRef::Next, span: 23..28 },
+            SignatureError::UnknownName { name: "user".to_string(), span: 29..35 },
+        ]),
+        check("{price:w$?} {} {width} {:0$} {user}")
+    );
+
+    let errors = check("ab {price:x}").unwrap_err();
+    assert_eq!(3..12, errors[0].span());
+    assert_eq!(
+        "argument `price` does not support the format `:x` at offset 3",
+        errors[0].to_string()
+    );
+}
+
+#[test]
+fn segment_spans() {
+    use rt_format::parser::{MissingBehavior, Parser, Substitution};
+
+    let named = [("a", Variant::Int(1))];
+    let segments: Result<Vec<Segment<Variant>>, _> =
+        Parser::new("x{a:>4}{b} }}", &NoPositionalArguments, &named)
+            .with_missing_behavior(MissingBehavior::Passthrough)
+            .collect();
+    let segments = segments.unwrap();
+    assert_eq!(
+        vec![0..1, 1..7, 7..10, 10..11, 11..13],
+        segments.iter().map(|segment| segment.span()).collect::<Vec<_>>()
+    );
+    assert!(matches!(&segments[1], Segment::Substitution(sub) if sub.span() == (1..7)));
+    assert!(matches!(segments[2], Segment::Text { text: "{b}", .. }));
+
+    let substitution = Substitution::new(Specifier::default(), &named[0].1).unwrap();
+    assert_eq!(None, substitution.argument_ref());
+    assert_eq!(0..0, Segment::Substitution(substitution).span());
+}
+
+#[test]
+fn brace_heavy_formats() {
+    let args = [Variant::Int(42), Variant::Int(7)];
+    let format = |format| parse(format, &args, &NoNamedArguments).map(|parsed| parsed.to_string());
+    macro_rules! assert_parity {
+        ($($format:literal),+ $(,)?) => {
+            $(assert_eq!(Ok(format!($format, 42, 7)), format($format), "{}", $format);)+
+        };
+    }
+    assert_parity!(
+        "{{{}}}{}",
+        "{{{0}}}{1}",
+        "{{{}}}}}{}",
+        "{{{{{}}}}}{}",
+        "}}{}{{{}",
+        "{}{{}}{}",
+        "{{{0:>4}}}{1:<3}",
+        "{{{1}}}{{{0}}}",
+        "{{{{}}}}{}{}",
+        "}}}}{{{{{}{}",
+        "a{{b}}c{0}d{{{1}",
+        "{{{}{}",
+    );
+    for bad in ["{}}}}", "{{{", "}}}", "{{}", "{0}}", "{{0}}}", "{}{", "}{}", "{{{0}}",
+        "{:{<3}", "{:}>3}", "{:}}>3}"] {
+        assert!(format(bad).is_err(), "{}", bad);
+    }
+}
@@ -0,0 +1,72 @@
+use rt_format::prelude::*;
+
+fn fmt_primitives(format: &str, args: &[Primitive]) -> Result<String, rt_format::ParseError> {
+    ParsedFormat::parse(format, args, &NoNamedArguments).map(|parsed| parsed.to_string())
+}
+
+#[test]
+fn primitive_formats() {
+    let args: Vec<Primitive> = vec![
+        (-5i8).into(),
+        200u8.into(),
+        u128::MAX.into(),
+        1.5f32.into(),
+        (-0.25f64).into(),
+        true.into(),
+        'x'.into(),
+        "text".into(),
+    ];
+    assert_eq!(
+        Ok(format!(
+            "{:+} {:#x} {:X} {:.2} {:e} {} {:?} {:?}",
+            -5i8,
+            200u8,
+            u128::MAX,
+            1.5f32,
+            -0.25f64,
+            true,
+            'x',
+            "text"
+        )),
+        fmt_primitives("{:+} {:#x} {:X} {:.2} {:e} {} {:?} {:?}", &args)
+    );
+    assert_eq!(Ok("0b11001000 2e2".to_string()), fmt_primitives("{1:#b} {1:.0e}", &args));
+}
+
+#[test]
+fn primitive_unsupported_formats() {
+    assert!(fmt_primitives("{:x}", &[1.5.into()]).is_err());
+    assert!(fmt_primitives("{:o}", &[true.into()]).is_err());
+    assert!(fmt_primitives("{:e}", &["text".into()]).is_err());
+    assert!(fmt_primitives("{:p}", &[42.into()]).is_err());
+    assert!(fmt_primitives("{:t}", &['x'.into()]).is_err());
+}
+
+#[test]
+fn primitive_alignment() {
+    let args: Vec<Primitive> =
+        vec![42u64.into(), 1.5.into(), "ab".into(), 'c'.into(), false.into()];
+    assert_eq!(
+        Ok(format!("[{:4}] [{:4}] [{:4}] [{:4}] [{:6}]", 42u64, 1.5, "ab", 'c', false)),
+        fmt_primitives("[{:4}] [{:4}] [{:4}] [{:4}] [{:6}]", &args)
+    );
+}
+
+#[test]
+fn primitive_size() {
+    let args: Vec<Primitive> = vec![2.75.into(), 6u16.into(), 2i64.into(), (-1i32).into()];
+    assert_eq!(Ok("  2.75".to_string()), fmt_primitives("{0:1$.2$}", &args));
+    assert!(fmt_primitives("{0:3$}", &args).is_err());
+    assert!(fmt_primitives("{1:0$}", &args).is_err());
+}
+
+#[test]
+fn char_alignment() {
+    for c in ['x', 'é', '→', '😀'] {
+        let args: Vec<Primitive> = vec![c.into()];
+        assert_eq!(
+            Ok(format!("[{:5}] [{:<5}] [{:^5}] [{:>5}] [{:*^4}] [{:~>3?}]", c, c, c, c, c, c)),
+            fmt_primitives("[{:5}] [{0:<5}] [{0:^5}] [{0:>5}] [{0:*^4}] [{0:~>3?}]", &args)
+        );
+    }
+}
@@ -0,0 +1,47 @@
+#![cfg(feature = "serde")]
+
+use rt_format::argument::ArgumentSource;
+use rt_format::parser::parse_specifier;
+use rt_format::{Align, Format, Specifier, Width};
+use serde_json::json;
+
+mod common;
+use common::Variant;
+
+struct NoValues;
+
+impl ArgumentSource<Variant> for NoValues {
+    fn next_argument(&mut self) -> Option<&Variant> { None }
+    fn lookup_argument_by_index(&self, _: usize) -> Option<&Variant> { None }
+    fn lookup_argument_by_name(&self, _: &str) -> Option<&Variant> { None }
+}
+
+#[test]
+fn serialize_specifier() {
+    let specifier = Specifier {
+        align: Align::Right,
+        width: Width::AtLeast { width: 8 },
+        format: Format::LowerHex,
+        ..Default::default()
+    };
+    let expected = json!({
+        "fill": null,
+        "grouping": "None",
+        "max_width": "Unlimited",
+        "align": "Right",
+        "sign": "Default",
+        "repr": "Default",
+        "pad": "Space",
+        "width": { "AtLeast": { "width": 8 } },
+        "precision": "Auto",
+        "format": "LowerHex",
+    });
+    assert_eq!(expected, serde_json::to_value(specifier).unwrap());
+}
+
+#[test]
+fn round_trip_specifier() {
+    let parsed = parse_specifier("*^+#12,…10.3e", &mut NoValues).unwrap();
+    let json = serde_json::to_string(&parsed).unwrap();
+    assert_eq!(parsed, serde_json::from_str::<Specifier>(&json).unwrap());
+}
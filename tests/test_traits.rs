@@ -16,4 +16,59 @@ fn specifier_traits() {
         format: Format::UpperExp,
         ..Default::default()
     }));
+    assert_eq!("*<8", format!("{}", Specifier {
+        fill: Some('*'),
+        align: Align::Left,
+        width: Width::AtLeast { width: 8 },
+        ..Default::default()
+    }));
+}
+
+#[test]
+fn specifier_builder() {
+    assert_eq!(
+        Specifier {
+            fill: Some('*'),
+            align: Align::Right,
+            sign: Sign::Always,
+            repr: Repr::Alt,
+            width: Width::AtLeast { width: 8 },
+            precision: Precision::Exactly { precision: 2 },
+            format: Format::LowerHex,
+            ..Default::default()
+        },
+        Specifier::builder()
+            .fill('*')
+            .align(Align::Right)
+            .sign(Sign::Always)
+            .repr(Repr::Alt)
+            .width(8)
+            .precision(2)
+            .format(Format::LowerHex)
+            .build()
+    );
+    assert_eq!(Specifier::default(), Specifier::builder().build());
+    assert_eq!(Pad::Zero, Specifier::builder().pad(Pad::Zero).build().pad);
+}
+
+#[test]
+fn specifier_hash() {
+    use std::collections::hash_map::DefaultHasher;
+    use std::collections::HashMap;
+    use std::hash::{Hash, Hasher};
+
+    fn hash_of(specifier: &Specifier) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        specifier.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    let first: Specifier = "*^+#12,.3e".parse().unwrap();
+    let second: Specifier = "*^+#12,.3e".parse().unwrap();
+    assert_eq!(first, second);
+    assert_eq!(hash_of(&first), hash_of(&second));
+
+    let mut cache = HashMap::new();
+    cache.insert(first, "cached");
+    assert_eq!(Some(&"cached"), cache.get(&second));
 }